@@ -3,62 +3,116 @@
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
 
-use cty::{c_char, c_double, c_float, c_int, c_long, c_short, c_uchar, c_ulong, c_void, uint16_t, uint32_t, uint8_t};
+use cty::{c_char, c_double, c_float, c_int, c_longlong, c_short, c_uchar, c_ulong, c_void, size_t, uint16_t, uint32_t, uint8_t};
 use volatile::Volatile;
 
-use crate::{Audio::fill_buffer_callback, Controller::{ControllerData, ControllerOriginData, N64Controller, GCController}, Directory::{DirType}, Display::{AntiAlias, BitDepth, DisplayContext, Gamma, Resolution}, Exceptions::{Exception, ExceptionCode, ExceptionType, RegisterBlock}, GraphicsEngine::{RGBColor, Sprite}, Interrupt::{InterruptState}, MemoryPak::{EntryStructure}, N64System::{TVType}, RDP::{Sync, Flush, Mirror}, Timer::{TimerLink}, TransferPak::{GBCSupportType, GBCTitle, GameboyCartridgeHeader, NewTitle, OldTitle}};
+use crate::{Audio::fill_buffer_callback, Controller::{ControllerData, ControllerOriginData, N64Controller, GCController}, Directory::{DirType}, Display::{AntiAlias, BitDepth, DisplayContext, Gamma, Resolution}, Exceptions::{Exception, ExceptionCode, ExceptionType, RegisterBlock}, GraphicsEngine::{RGBColor, Sprite}, Interrupt::{InterruptState}, MemoryPak::{EntryStructure}, N64System::{TVType}, RDP::{Sync, Flush, Mirror}, RealTimeClock::{RtcTime}, Timer::{TimerLink}, TransferPak::{GBCSupportType, GBCTitle, GameboyCartridgeHeader, NewTitle, OldTitle}};
 
 /*
     cop0.h defines
+
+    The register macros only touch the coprocessors on the N64 (target_arch "mips").
+    Elsewhere, i.e. host builds for unit tests, reads give 0 and writes do nothing.
  */
+/// Read the COP0 Count register into $x, a u32 place such as an uninitialized local.
 #[macro_export]
 macro_rules! C0_COUNT {
-    ($x:expr) => { unsafe { asm!("mfc0 {0},$9", out(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("mfc0 {0},$9", out(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { $x = 0; }
+    }}
 }
 
 #[macro_export]
 macro_rules! C0_WRITE_COUNT {
-    ($x: expr) => { unsafe { asm!("mtc0 {0},$9", in(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("mtc0 {0},$9", in(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { let _ = $x; }
+    }}
 }
 
 #[macro_export]
 macro_rules! C0_COMPARE {
-    ($x: expr) => { unsafe { asm!("mfc0 {0},$11", out(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("mfc0 {0},$11", out(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { $x = 0; }
+    }}
 }
 
 #[macro_export]
 macro_rules! C0_WRITE_COMPARE {
-    ($x: expr) => { unsafe { asm!("mtc0 {0},$11", in(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("mtc0 {0},$11", in(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { let _ = $x; }
+    }}
 }
 
 #[macro_export]
 macro_rules! C0_STATUS {
-    ($x: expr) => { unsafe { asm!("mfc0 {0},$12", out(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("mfc0 {0},$12", out(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { $x = 0; }
+    }}
 }
 
 #[macro_export]
 macro_rules! C0_WRITE_STATUS {
-    ($x: expr) => { unsafe { asm!("mtc0 {0},$12", in(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("mtc0 {0},$12", in(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { let _ = $x; }
+    }}
 }
 
 #[macro_export]
 macro_rules! C0_READ_CR {
-    ($x: expr) => { unsafe { asm!("mfc0 {0},$13", out(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("mfc0 {0},$13", out(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { $x = 0; }
+    }}
 }
 
 #[macro_export]
 macro_rules! C0_WRITE_CR {
-    ($x: expr) => { unsafe { asm!("mtc0 {0},$13", in(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("mtc0 {0},$13", in(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { let _ = $x; }
+    }}
 }
 
 #[macro_export]
 macro_rules! C0_READ_BADVADDR {
-    ($x: expr) => { unsafe { asm!("mfc0 {0},$8", out(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("mfc0 {0},$8", out(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { $x = 0; }
+    }}
 }
 
 #[macro_export]
 macro_rules! C0_READ_EPC {
-    ($x: expr) => { unsafe { asm!("mfc0 {0},$14", out(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("mfc0 {0},$14", out(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { $x = 0; }
+    }}
 }
 
 #[macro_export]
@@ -76,12 +130,22 @@ macro_rules! C0_GET_CAUSE_EXC_CODE {
  */
 #[macro_export]
 macro_rules! C1_FCR31 {
-    ($x: expr) => { unsafe { asm!("cfc1 {0},$31", out(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("cfc1 {0},$31", out(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { $x = 0; }
+    }}
 }
 
 #[macro_export]
 macro_rules! C1_WRITE_FCR31 {
-    ($x: expr) => { unsafe { asm!("ctc1 {0},$31", in(reg) $x); }}
+    ($x: expr) => {{
+        #[cfg(target_arch = "mips")]
+        unsafe { asm!("ctc1 {0},$31", in(reg) $x); }
+        #[cfg(not(target_arch = "mips"))]
+        { let _ = $x; }
+    }}
 }
 
 /*
@@ -151,6 +215,24 @@ macro_rules! get_ticks {
     ($x:expr) => { (crate::TICKS_READ!($x)) }
 }
 
+/*
+    rsp.h defines
+ */
+#[macro_export]
+macro_rules! SP_DMEM {
+    () => { (0xA400_0000 as *mut u32) }
+}
+
+#[macro_export]
+macro_rules! SP_STATUS {
+    () => { (0xA404_0010 as *mut u32) }
+}
+
+#[macro_export]
+macro_rules! SP_PC {
+    () => { (0xA408_0000 as *mut u32) }
+}
+
 /*
     timer.h defines
  */
@@ -177,7 +259,7 @@ macro_rules! TIMER_MICROS_LL {
 /*
     C function interface
  */
-#[link(name = "dragon", kind = "static")]
+#[cfg_attr(target_arch = "mips", link(name = "dragon", kind = "static"))]
 extern "C" {
 
     /*
@@ -229,6 +311,21 @@ extern "C" {
     // void console_render();
     pub(crate) fn console_render();
 
+    /*
+        newlib functions (system.c)
+     */
+    // int write( int file, char *ptr, int len );
+    pub(crate) fn write(file: c_int, ptr: *const c_char, len: c_int) -> c_int;
+
+    /*
+        newlib functions (malloc.h)
+     */
+    // void *memalign( size_t alignment, size_t size );
+    pub(crate) fn memalign(alignment: size_t, size: size_t) -> *mut c_void;
+
+    // void free( void *ptr );
+    pub(crate) fn free(ptr: *mut c_void);
+
     /*
         controller.h functions
      */
@@ -707,7 +804,7 @@ extern "C" {
     pub(crate) fn timer_close();
 
     // long long timer_ticks(void);
-    pub(crate) fn timer_ticks() -> c_long;
+    pub(crate) fn timer_ticks() -> c_longlong;
 
     /*
         exception.h functions
@@ -726,6 +823,26 @@ extern "C" {
 
     // int dir_findnext( const char * const path, dir_t *dir );
     pub(crate) fn dir_findnext(path: *const c_char, dir: *mut dir_t) -> c_int;
+
+    /*
+        rtc.h functions
+     */
+    // bool rtc_init( void );
+    pub(crate) fn rtc_init() -> bool;
+
+    // bool rtc_get( rtc_time_t * rtc_time );
+    pub(crate) fn rtc_get(rtc_time: *mut rtc_time_t) -> bool;
+
+    // bool rtc_set( rtc_time_t * write_time );
+    pub(crate) fn rtc_set(write_time: *mut rtc_time_t) -> bool;
+}
+
+/*
+    n64.ld symbols
+ */
+extern "C" {
+    // First byte past .bss, where libdragon's sbrk() starts the heap.
+    pub(crate) static end: u8;
 }
 
 /*
@@ -806,4 +923,9 @@ type reg_block_t = RegisterBlock;
 /*
     dir.h types
  */
-type dir_t = DirType;
\ No newline at end of file
+type dir_t = DirType;
+
+/*
+    rtc.h types
+ */
+type rtc_time_t = RtcTime;
\ No newline at end of file
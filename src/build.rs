@@ -1,4 +1,7 @@
 fn main() {
-    println!("cargo:rustc-link-lib=dragon");
-    println!("cargo:rustc-link-search=native=/usr/local/mips64-elf/lib")
-}
\ No newline at end of file
+    // libdragon only exists for the N64; host builds (unit tests) don't link it.
+    if std::env::var("CARGO_CFG_TARGET_ARCH").map_or(false, |arch| arch == "mips") {
+        println!("cargo:rustc-link-lib=dragon");
+        println!("cargo:rustc-link-search=native=/usr/local/mips64-elf/lib")
+    }
+}
@@ -3,7 +3,7 @@
 //!
 //! Module and method documentation taken from the libdragon doxygen
 //! documentation at https://dragonminded.com/n64dev/libdragon/doxygen/
-#![no_std]
+#![cfg_attr(not(test), no_std)]
 
 #![allow(non_camel_case_types)]
 #![allow(non_snake_case)]
@@ -12,6 +12,320 @@
 
 mod bindings;
 
+/// An axis-aligned rectangle in screen pixels.
+///
+/// (x, y) is the top left corner and w/h the size, so the rectangle covers
+/// x..x + w horizontally and y..y + h vertically. Shared by the software
+/// (GraphicsEngine) and hardware (RDP) drawing paths.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct Rect {
+    pub x: i32,
+    pub y: i32,
+    pub w: i32,
+    pub h: i32
+}
+
+impl Rect {
+    pub const fn new(x: i32, y: i32, w: i32, h: i32) -> Rect {
+        return Rect { x, y, w, h };
+    }
+
+    /// Build a rectangle from its top left and (exclusive) bottom right corners.
+    pub const fn from_corners(top_left: (i32, i32), bottom_right: (i32, i32)) -> Rect {
+        return Rect::new(top_left.0, top_left.1, bottom_right.0 - top_left.0, bottom_right.1 - top_left.1);
+    }
+
+    /// The top left corner.
+    pub const fn top_left(&self) -> (i32, i32) {
+        return (self.x, self.y);
+    }
+
+    /// The bottom right corner, one past the last covered pixel.
+    pub const fn bottom_right(&self) -> (i32, i32) {
+        return (self.x + self.w, self.y + self.h);
+    }
+
+    /// Return whether the rectangle covers no pixels.
+    pub const fn is_empty(&self) -> bool {
+        return self.w <= 0 || self.h <= 0;
+    }
+
+    /// Return whether the pixel at (x, y) lies inside the rectangle.
+    pub const fn contains_point(&self, x: i32, y: i32) -> bool {
+        return x >= self.x && x < self.x + self.w && y >= self.y && y < self.y + self.h;
+    }
+
+    /// Return whether other lies entirely inside this rectangle.
+    pub const fn contains(&self, other: &Rect) -> bool {
+        return other.x >= self.x && other.y >= self.y
+            && other.x + other.w <= self.x + self.w
+            && other.y + other.h <= self.y + self.h;
+    }
+
+    /// Return the overlapping area of two rectangles, or None if they don't overlap.
+    pub fn intersection(&self, other: &Rect) -> Option<Rect> {
+        let top_left = (core::cmp::max(self.x, other.x), core::cmp::max(self.y, other.y));
+        let bottom_right = (
+            core::cmp::min(self.x + self.w, other.x + other.w),
+            core::cmp::min(self.y + self.h, other.y + other.h)
+        );
+        let overlap = Rect::from_corners(top_left, bottom_right);
+
+        if overlap.is_empty() {
+            return None;
+        }
+
+        return Some(overlap);
+    }
+}
+
+/// The video/clock standard of the console.
+///
+/// PAL, NTSC and MPAL consoles run their video and audio clocks at different
+/// rates. This gathers the region-dependent numbers in one place; use current()
+/// to get the region the boot code reported.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum Region {
+    Pal,  // Europe, Australia
+    Ntsc, // North America, Japan
+    Mpal  // Brazil
+}
+
+impl Region {
+    /// The region of the running console, as reported by N64System::get_tv_type().
+    pub fn current() -> Region {
+        return Region::from_tv_type(N64System::get_tv_type());
+    }
+
+    pub const fn from_tv_type(tv_type: N64System::TVType) -> Region {
+        return match tv_type {
+            N64System::TVType::TV_PAL => Region::Pal,
+            N64System::TVType::TV_NTSC => Region::Ntsc,
+            N64System::TVType::TV_MPAL => Region::Mpal
+        };
+    }
+
+    pub const fn tv_type(&self) -> N64System::TVType {
+        return match self {
+            Region::Pal => N64System::TVType::TV_PAL,
+            Region::Ntsc => N64System::TVType::TV_NTSC,
+            Region::Mpal => N64System::TVType::TV_MPAL
+        };
+    }
+
+    /// Vertical refresh rate in Hz.
+    pub const fn refresh_hz(&self) -> u32 {
+        return match self {
+            Region::Pal => 50,
+            Region::Ntsc | Region::Mpal => 60
+        };
+    }
+
+    /// Video clock in Hz that the audio DAC rate is divided from.
+    pub const fn audio_clock(&self) -> u32 {
+        return match self {
+            Region::Pal => 49656530,
+            Region::Ntsc => 48681812,
+            Region::Mpal => 48628316
+        };
+    }
+
+    /// The playback rate libdragon's audio_init() reports for frequency, or 0 for 0.
+    ///
+    /// The DAC can only divide audio_clock() by an integer, so the rate differs
+    /// slightly from the one asked for. This uses the same formula as
+    /// audio_init(), so it matches Audio::get_frequency() after init.
+    pub const fn actual_audio_frequency(&self, frequency: u32) -> u32 {
+        if frequency == 0 {
+            return 0;
+        }
+
+        let clock = self.audio_clock();
+
+        return 2 * clock / (2 * clock / frequency + 1);
+    }
+
+    /// The standard playback rate (32000, 44100 or 48000Hz) this region's DAC reproduces most closely.
+    ///
+    /// Picks the rate whose actual_audio_frequency() is off by the smallest
+    /// fraction of itself, from audio_clock(): 44100Hz on PAL and NTSC, 32000Hz
+    /// on MPAL.
+    pub fn recommended_audio_frequency(&self) -> u32 {
+        let error_ppm = |frequency: &u32| {
+            let actual = self.actual_audio_frequency(*frequency);
+
+            return (*frequency - actual) as u64 * 1_000_000 / *frequency as u64;
+        };
+
+        return [32000, 44100, 48000].iter().copied().min_by_key(error_ppm).unwrap();
+    }
+
+    /// The resolution to use when a program has no preference.
+    ///
+    /// libdragon only offers NTSC-sized modes and letterboxes them on PAL, so
+    /// this is 320x240 for every region.
+    pub const fn default_resolution(&self) -> Display::Resolution {
+        return Display::Resolution::RESOLUTION_320x240;
+    }
+}
+
+/// A return code from libdragon outside the values it documents.
+///
+/// Returned by the try_ variants of wrappers that would otherwise panic, so a
+/// flaky accessory or corrupt data can be logged and recovered from.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub struct UnexpectedCode(pub i32);
+
+impl core::fmt::Display for UnexpectedCode {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        return write!(f, "unexpected return code {}", self.0);
+    }
+}
+
+/// An error from any subsystem.
+///
+/// Each module keeps its own error type; this wraps them so code that touches
+/// several subsystems can use ? across all of them.
+#[derive(Debug)]
+pub enum Error {
+    Controller(Controller::SIError),
+    MemPak(Controller::MemPakResult),
+    Eeprom(Controller::EepromError),
+    TransferPak(TransferPak::TPakError),
+    Dma(DMA::DmaError),
+    DragonFS(DragonFS::DFSResult),
+    Directory(cty::c_int), // Negative result from the Directory functions
+    Rdp(RDP::RdpError),
+    Rsp(RSP::RspError),
+    Timer(Timer::TimerError),
+    Unexpected(UnexpectedCode)
+}
+
+impl From<Controller::SIError> for Error {
+    fn from(error: Controller::SIError) -> Error {
+        return Error::Controller(error);
+    }
+}
+
+impl From<Controller::MemPakResult> for Error {
+    fn from(error: Controller::MemPakResult) -> Error {
+        return Error::MemPak(error);
+    }
+}
+
+impl From<Controller::EepromError> for Error {
+    fn from(error: Controller::EepromError) -> Error {
+        return Error::Eeprom(error);
+    }
+}
+
+impl From<TransferPak::TPakError> for Error {
+    fn from(error: TransferPak::TPakError) -> Error {
+        return Error::TransferPak(error);
+    }
+}
+
+impl From<DMA::DmaError> for Error {
+    fn from(error: DMA::DmaError) -> Error {
+        return Error::Dma(error);
+    }
+}
+
+impl From<DragonFS::DFSResult> for Error {
+    fn from(error: DragonFS::DFSResult) -> Error {
+        return Error::DragonFS(error);
+    }
+}
+
+impl From<RDP::RdpError> for Error {
+    fn from(error: RDP::RdpError) -> Error {
+        return Error::Rdp(error);
+    }
+}
+
+impl From<RSP::RspError> for Error {
+    fn from(error: RSP::RspError) -> Error {
+        return Error::Rsp(error);
+    }
+}
+
+impl From<Timer::TimerError> for Error {
+    fn from(error: Timer::TimerError) -> Error {
+        return Error::Timer(error);
+    }
+}
+
+impl From<UnexpectedCode> for Error {
+    fn from(error: UnexpectedCode) -> Error {
+        return Error::Unexpected(error);
+    }
+}
+
+impl core::fmt::Display for Error {
+    fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+        return match self {
+            Error::Controller(error) => write!(f, "Controller error: {}", error),
+            Error::MemPak(error) => write!(f, "Memory pak error: {}", error),
+            Error::Eeprom(error) => write!(f, "EEPROM error: {:?}", error),
+            Error::TransferPak(error) => write!(f, "Transfer pak error: {}", error),
+            Error::Dma(error) => write!(f, "DMA error: {:?}", error),
+            Error::DragonFS(error) => write!(f, "DragonFS error: {}", error),
+            Error::Directory(error) => write!(f, "Directory error: {}", error),
+            Error::Rdp(error) => write!(f, "RDP error: {:?}", error),
+            Error::Rsp(error) => write!(f, "RSP error: {:?}", error),
+            Error::Timer(error) => write!(f, "Timer error: {:?}", error),
+            Error::Unexpected(error) => write!(f, "libdragon returned an {}", error)
+        };
+    }
+}
+
+/// Panic reporting.
+#[cfg(feature = "console_panic")]
+pub mod panic {
+    use core::panic::PanicInfo;
+
+    use crate::{Console, Display};
+
+    /// Show a panic on the console and halt.
+    ///
+    /// Call this from the program's #[panic_handler]. The console is brought
+    /// up if it isn't already, closing the display first as the two can't be
+    /// used together. Showing the console needs a free display buffer, which
+    /// in turn needs the VI interrupt, so a panic raised with interrupts
+    /// disabled may hang before anything is shown.
+    pub fn console_panic_handler(info: &PanicInfo) -> ! {
+        if !Console::is_initialized() {
+            if Display::is_initialized() {
+                Display::close();
+            }
+
+            Console::init();
+        }
+
+        Console::set_render_mode(Console::RenderMode::RenderManual);
+        Console::clear();
+        crate::console_println!("{}", info);
+        Console::render();
+
+        loop {}
+    }
+}
+
+/// The types most programs need, for glob importing.
+///
+/// `use libdragon_bindings::prelude::*;` brings in the subsystem modules along
+/// with the handle, color, geometry and guard types used across them.
+pub mod prelude {
+    pub use crate::{Audio, Console, Controller, Display, DMA, DragonFS, GraphicsEngine, Interrupt, N64System, RDP, RSP, Timer, Directory};
+    pub use crate::{Error, Rect, Region};
+    pub use crate::Controller::{Button, ButtonSet, ControllerNum};
+    pub use crate::Display::{AntiAlias, BitDepth, DisplayContext, Gamma, LockedContext, Resolution};
+    pub use crate::DragonFS::{DFSResult, File, SeekFrom};
+    pub use crate::GraphicsEngine::{N64Color, RGBColor, Sprite};
+    pub use crate::RDP::RdpFrame;
+    pub use crate::Timer::{ClosureTimer, Stopwatch, Timer as TimerGuard}; // Timer is taken by the module
+}
+
 /// Interface to the N64 audio hardware.
 ///
 /// The audio subsystem handles queueing up chunks of audio data for playback
@@ -41,7 +355,7 @@ mod bindings;
 pub mod Audio {
     use cty::*;
 
-    use crate::bindings;
+    use crate::{N64System, Region, bindings};
 
     pub type Frequency = c_int;
     pub type fill_buffer_callback = Option<extern "C" fn(buffer: *mut c_short, numsamples: size_t)>;
@@ -56,6 +370,31 @@ pub mod Audio {
         unsafe {bindings::audio_init(frequency, numbuffers)}
     }
 
+    /// Initialize the audio subsystem at recommended_frequency().
+    ///
+    /// Returns the frequency that will actually be played back, which differs
+    /// slightly from the requested one.
+    pub fn init_for_region(numbuffers: i32) -> Frequency {
+        init(recommended_frequency(), numbuffers);
+
+        return get_frequency();
+    }
+
+    /// The standard playback frequency the running console's DAC reproduces most closely.
+    ///
+    /// Reads N64System::get_tv_type() and returns, per region:
+    ///
+    /// * PAL: 44100Hz (plays at 44099Hz)
+    /// * NTSC: 44100Hz (plays at 44095Hz)
+    /// * MPAL: 32000Hz (plays at 31992Hz; 44100Hz would be 44087Hz)
+    ///
+    /// See Region::recommended_audio_frequency().
+    pub fn recommended_frequency() -> Frequency {
+        let region = Region::from_tv_type(N64System::get_tv_type());
+
+        return region.recommended_audio_frequency() as Frequency;
+    }
+
     /// Set callback function for when the audio buffer is empty and needs more sample data
     pub fn set_buffer_callback(fill_buffer_callback: fill_buffer_callback) {
         unsafe { bindings::audio_set_buffer_callback(fill_buffer_callback) };
@@ -123,6 +462,101 @@ pub mod Audio {
     pub fn get_buffer_length() -> i32 {
         unsafe { return bindings::audio_get_buffer_length(); }
     }
+
+    /// Get the size in bytes of an allocated buffer, i.e. get_buffer_length() * 2 * sizeof( short ).
+    pub fn get_buffer_length_bytes() -> usize {
+        return get_buffer_length() as usize * 2 * core::mem::size_of::<i16>();
+    }
+
+    /// Return whether the console's DAC can play back at frequency.
+    ///
+    /// The DAC divides the region's audio clock by an integer that must fit the
+    /// 14-bit AI_DACRATE register, which bounds the frequency from below
+    /// (to roughly 3kHz). Check this before init(), which doesn't validate.
+    pub fn frequency_is_valid(frequency: Frequency) -> bool {
+        if frequency <= 0 {
+            return false;
+        }
+
+        let clock = Region::current().audio_clock();
+        let divider = (2 * clock / frequency as u32 + 1) / 2;
+
+        return divider >= 1 && divider - 1 <= 0x3FFF;
+    }
+
+    /// Most stereo samples per buffer a Stream can hold, enough for 48kHz playback.
+    pub const STREAM_BUFFER_SAMPLES: usize = 2048;
+
+    /// Streams audio produced by a closure into the output buffers.
+    ///
+    /// libdragon's buffer callback takes no context pointer, so a Stream isn't
+    /// driven from the audio interrupt. Instead, call pump() regularly from the
+    /// main loop (at least once per buffer's worth of playback) to keep the
+    /// buffers full. The scratch buffer lives inside the Stream, which makes it
+    /// about 8KB.
+    ///
+    /// ```no_run
+    /// use libdragon_bindings::Audio;
+    ///
+    /// // One period of a sine wave; at 44100Hz this plays a 2756Hz tone.
+    /// const SINE: [i16; 16] = [
+    ///     0, 6270, 11585, 15137, 16384, 15137, 11585, 6270,
+    ///     0, -6270, -11585, -15137, -16384, -15137, -11585, -6270
+    /// ];
+    ///
+    /// Audio::init(44100, 2);
+    ///
+    /// let mut phase = 0;
+    /// let mut stream = Audio::Stream::new(|buffer: &mut [i16]| {
+    ///     for frame in buffer.chunks_exact_mut(2) {
+    ///         frame[0] = SINE[phase]; // Left
+    ///         frame[1] = SINE[phase]; // Right
+    ///         phase = (phase + 1) % SINE.len();
+    ///     }
+    /// }).unwrap();
+    ///
+    /// // Both buffers start out free, so the first pump fills them.
+    /// assert_eq!(stream.pump(), 2);
+    ///
+    /// // From here on, call stream.pump() once per frame of the main loop.
+    /// ```
+    pub struct Stream<F: FnMut(&mut [i16])> {
+        fill: F,
+        scratch: [i16; STREAM_BUFFER_SAMPLES * 2],
+        len: usize
+    }
+
+    impl<F: FnMut(&mut [i16])> Stream<F> {
+        /// Create a stream for the initialized audio subsystem.
+        ///
+        /// fill is given interleaved stereo samples to fill, get_buffer_length()
+        /// pairs at a time. Returns None if the buffers are longer than
+        /// STREAM_BUFFER_SAMPLES.
+        pub fn new(fill: F) -> Option<Stream<F>> {
+            let samples = get_buffer_length() as usize;
+
+            if samples > STREAM_BUFFER_SAMPLES {
+                return None;
+            }
+
+            return Some(Stream { fill, scratch: [0; STREAM_BUFFER_SAMPLES * 2], len: samples * 2 });
+        }
+
+        /// Fill and write buffers until none are free, returning how many were written.
+        pub fn pump(&mut self) -> usize {
+            let mut written = 0;
+
+            while can_write() != 0 {
+                let buffer = &mut self.scratch[..self.len];
+                (self.fill)(buffer);
+                write_buffer(buffer);
+
+                written += 1;
+            }
+
+            return written;
+        }
+    }
 }
 
 /// Software console emulation for debugging and simple text output.
@@ -142,6 +576,8 @@ pub mod Audio {
 /// use clear(). Once the console is not needed or when the code wishes to switch to the display
 /// subsystem, clear() should be called to cleanly shut down the console support.
 pub mod Console {
+    use core::sync::atomic::{AtomicBool, Ordering};
+
     use crate::bindings;
 
     #[repr(i32)]
@@ -159,14 +595,25 @@ pub mod Console {
     /// Initialize the console system. This will initialize the
     /// video properly, so a call to the display_init() function is not necessary.
     pub fn init() {
+        INITIALIZED.store(true, Ordering::Relaxed);
+
         unsafe { bindings::console_init(); }
     }
 
     /// Free the console system. This will clean up any dynamic memory that was in use.
     pub fn close() {
+        INITIALIZED.store(false, Ordering::Relaxed);
+
         unsafe { bindings::console_close(); }
     }
 
+    static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+    /// Return whether init() has been called without a later close().
+    pub fn is_initialized() -> bool {
+        return INITIALIZED.load(Ordering::Relaxed);
+    }
+
     /// This sets the render mode of the console. The RenderAutomatic
     /// mode allows console_printf to immediately be placed onto the screen.
     /// This is very similar to a normal console on a unix/windows system.
@@ -190,6 +637,62 @@ pub mod Console {
     pub fn render() {
         unsafe { bindings::console_render(); }
     }
+
+    // File descriptor libdragon routes to the console once it is initialized.
+    const STDOUT: i32 = 1;
+
+    /// Print text to the console.
+    ///
+    /// This goes through newlib's stdout, which libdragon hooks up to the
+    /// console in init(), so it is what printf would print to. In manual render
+    /// mode the text appears at the next render().
+    pub fn print(text: &str) {
+        let mut bytes = text.as_bytes();
+
+        while !bytes.is_empty() {
+            let len = bytes.len().min(i32::MAX as usize);
+            let written = unsafe { bindings::write(STDOUT, bytes.as_ptr().cast(), len as i32) };
+
+            if written <= 0 {
+                return;
+            }
+
+            bytes = &bytes[written as usize..];
+        }
+    }
+
+    /// A core::fmt::Write sink that prints to the console, created by writer().
+    ///
+    /// Formatting writes each piece straight through print(), so nothing is allocated.
+    pub struct Writer;
+
+    impl core::fmt::Write for Writer {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            print(s);
+            return Ok(());
+        }
+    }
+
+    pub fn writer() -> Writer {
+        return Writer;
+    }
+
+    /// Print formatted text to the console, like print!.
+    #[macro_export]
+    macro_rules! console_print {
+        ($($arg:tt)*) => {
+            { let _ = core::fmt::Write::write_fmt(&mut $crate::Console::writer(), format_args!($($arg)*)); }
+        }
+    }
+
+    /// Print formatted text and a newline to the console, like println!.
+    #[macro_export]
+    macro_rules! console_println {
+        () => { $crate::Console::print("\n") };
+        ($($arg:tt)*) => {
+            { $crate::console_print!($($arg)*); $crate::Console::print("\n"); }
+        }
+    }
 }
 
 /// Controller and accessory interface
@@ -197,7 +700,7 @@ pub mod Controller {
     use bitfield::bitfield;
     use cty::*;
 
-    use crate::bindings;
+    use crate::{UnexpectedCode, bindings};
 
     #[repr(C)]
     pub struct ControllerData {
@@ -264,6 +767,183 @@ pub mod Controller {
         // }
     }
 
+    /// An N64 controller button, as a bit of ButtonSet.
+    #[repr(u16)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum Button {
+        A = 0x8000,
+        B = 0x4000,
+        Z = 0x2000,
+        Start = 0x1000,
+        DUp = 0x0800,
+        DDown = 0x0400,
+        DLeft = 0x0200,
+        DRight = 0x0100,
+        L = 0x0020,
+        R = 0x0010,
+        CUp = 0x0008,
+        CDown = 0x0004,
+        CLeft = 0x0002,
+        CRight = 0x0001
+    }
+
+    /// The buttons of an N64 controller, in the layout of the upper half of N64Controller::data().
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct ButtonSet(pub u16);
+
+    impl ButtonSet {
+        pub const fn bits(&self) -> u16 {
+            return self.0;
+        }
+
+        pub const fn contains(&self, button: Button) -> bool {
+            return self.0 & button as u16 != 0;
+        }
+
+        pub const fn is_empty(&self) -> bool {
+            return self.0 == 0;
+        }
+    }
+
+    macro_rules! button_accessors {
+        ($($name:ident = $button:ident, $doc:literal;)*) => {
+            $(
+                #[doc = $doc]
+                pub fn $name(&self) -> bool {
+                    return self.is_pressed(Button::$button);
+                }
+            )*
+        }
+    }
+
+    impl N64Controller {
+        /// The buttons set in this report.
+        pub fn pressed_buttons(&self) -> ButtonSet {
+            return ButtonSet((self.data() >> 16) as u16);
+        }
+
+        pub fn is_pressed(&self, button: Button) -> bool {
+            return self.pressed_buttons().contains(button);
+        }
+
+        button_accessors! {
+            is_a_pressed = A, "Whether A is set in this report.";
+            is_b_pressed = B, "Whether B is set in this report.";
+            is_z_pressed = Z, "Whether Z is set in this report.";
+            is_start_pressed = Start, "Whether Start is set in this report.";
+            is_d_up_pressed = DUp, "Whether D-pad up is set in this report.";
+            is_d_down_pressed = DDown, "Whether D-pad down is set in this report.";
+            is_d_left_pressed = DLeft, "Whether D-pad left is set in this report.";
+            is_d_right_pressed = DRight, "Whether D-pad right is set in this report.";
+            is_l_pressed = L, "Whether L is set in this report.";
+            is_r_pressed = R, "Whether R is set in this report.";
+            is_c_up_pressed = CUp, "Whether C-up is set in this report.";
+            is_c_down_pressed = CDown, "Whether C-down is set in this report.";
+            is_c_left_pressed = CLeft, "Whether C-left is set in this report.";
+            is_c_right_pressed = CRight, "Whether C-right is set in this report.";
+        }
+
+        /// Stick position as signed (x, y), with up and right positive.
+        pub fn stick(&self) -> (i8, i8) {
+            return (self.x() as u8 as i8, self.y() as u8 as i8);
+        }
+
+        /// Stick position scaled to -1.0..1.0 per axis over N64_STICK_RANGE.
+        ///
+        /// Axis values within deadzone of the center read as 0, and the rest of
+        /// the range is stretched so movement starts smoothly from 0 at its edge.
+        pub fn stick_deadzoned(&self, deadzone: u8) -> (f32, f32) {
+            let (x, y) = self.stick();
+
+            return (
+                normalize_axis(x as i32, deadzone, N64_STICK_RANGE),
+                normalize_axis(y as i32, deadzone, N64_STICK_RANGE)
+            );
+        }
+    }
+
+    /// Typical full deflection of an N64 stick; real sticks reach about 80 to 85.
+    pub const N64_STICK_RANGE: u8 = 80;
+
+    /// Typical full deflection of a GameCube main stick from its center.
+    pub const GC_STICK_RANGE: u8 = 100;
+
+    // Map a signed axis to -1.0..1.0, treating |value| <= deadzone as centered
+    // and saturating at range.
+    fn normalize_axis(value: i32, deadzone: u8, range: u8) -> f32 {
+        let magnitude = value.abs() - deadzone as i32;
+        let span = range as i32 - deadzone as i32;
+
+        if magnitude <= 0 || span <= 0 {
+            return 0.0;
+        }
+
+        let normalized = (magnitude as f32 / span as f32).min(1.0);
+
+        return match value < 0 {
+            true => -normalized,
+            false => normalized
+        };
+    }
+
+    impl GCController {
+        /// Main stick position as signed (x, y) around the nominal center of 128.
+        pub fn stick(&self) -> (i8, i8) {
+            return (center_axis(self.stick_x(), 128), center_axis(self.stick_y(), 128));
+        }
+
+        /// Main stick position relative to the center the controller reported at
+        /// calibration, which corrects for sticks that rest off-center.
+        pub fn stick_from_origin(&self, origin: &GCControllerOrigin) -> (i8, i8) {
+            return (
+                center_axis(self.stick_x(), origin.data.stick_x() as i32),
+                center_axis(self.stick_y(), origin.data.stick_y() as i32)
+            );
+        }
+
+        /// Main stick position scaled to -1.0..1.0 per axis over GC_STICK_RANGE.
+        ///
+        /// Measured from the calibrated origin when one is given (see
+        /// read_gc_origin_controller_data()), otherwise from the nominal center.
+        /// Deadzone handling is as for N64Controller::stick_deadzoned().
+        pub fn stick_deadzoned(&self, deadzone: u8, origin: Option<&GCControllerOrigin>) -> (f32, f32) {
+            let (x, y) = match origin {
+                Some(origin) => self.stick_from_origin(origin),
+                None => self.stick()
+            };
+
+            return (
+                normalize_axis(x as i32, deadzone, GC_STICK_RANGE),
+                normalize_axis(y as i32, deadzone, GC_STICK_RANGE)
+            );
+        }
+    }
+
+    fn center_axis(raw: u64, center: i32) -> i8 {
+        let value = raw as i32 - center;
+        return value.max(i8::MIN as i32).min(i8::MAX as i32) as i8;
+    }
+
+    impl ControllerData {
+        /// The N64 controller report for a controller port.
+        pub fn n64(&self, num: ControllerNum) -> &N64Controller {
+            return &self.c[num as usize];
+        }
+
+        /// The GameCube controller report for a controller port.
+        pub fn gc(&self, num: ControllerNum) -> &GCController {
+            return &self.gc[num as usize];
+        }
+
+        /// The buttons set in the N64 controller report for a controller port.
+        ///
+        /// Whether this means held, just pressed or just released depends on
+        /// which of the get_keys_* functions produced the data.
+        pub fn pressed_buttons(&self, num: ControllerNum) -> ButtonSet {
+            return self.n64(num).pressed_buttons();
+        }
+    }
+
     #[repr(C)]
     pub struct ControllerOriginData {
         pub gc: [GCControllerOrigin; 4]
@@ -292,13 +972,27 @@ pub mod Controller {
     }
 
     #[repr(i32)]
+    #[derive(Debug)]
     pub enum SIError {
         None = 0x0,       // No error occured
         BadCommand = 0x1, // Command not recognized or malformed
         NotPresent = 0x2  // Controller not present
     }
 
+    impl core::fmt::Display for SIError {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                SIError::None => "no error",
+                SIError::BadCommand => "command not recognized or malformed",
+                SIError::NotPresent => "controller not present"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[repr(i32)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum DPadDirection {
         R = 0,  // Right
         UR = 1, // Up + right
@@ -310,7 +1004,25 @@ pub mod Controller {
         DR = 7  // Down + right
     }
 
+    impl DPadDirection {
+        /// Convert a libdragon direction (0-7) to a DPadDirection, or None if out of range.
+        pub fn from_i32(value: i32) -> Option<DPadDirection> {
+            return match value {
+                0 => Some(DPadDirection::R),
+                1 => Some(DPadDirection::UR),
+                2 => Some(DPadDirection::U),
+                3 => Some(DPadDirection::UL),
+                4 => Some(DPadDirection::L),
+                5 => Some(DPadDirection::DL),
+                6 => Some(DPadDirection::D),
+                7 => Some(DPadDirection::DR),
+                _ => None
+            };
+        }
+    }
+
     #[repr(i32)]
+    #[derive(Debug)]
     pub enum MemPakResult {
         Success = 0,
         OutOfRange = -1,
@@ -318,7 +1030,21 @@ pub mod Controller {
         InvalidMemPakData = -3
     }
 
+    impl core::fmt::Display for MemPakResult {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                MemPakResult::Success => "success",
+                MemPakResult::OutOfRange => "address out of range",
+                MemPakResult::NoMemPak => "no mempak present",
+                MemPakResult::InvalidMemPakData => "invalid mempak data"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[repr(i32)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum ControllerNum {
         Controller1 = 0,
         Controller2 = 1,
@@ -349,6 +1075,36 @@ pub mod Controller {
         unsafe { bindings::controller_read_gc(data_out, &rumble); }
     }
 
+    /// Rumble state for GameCube controllers, kept between reads.
+    ///
+    /// The rumble motors follow whatever is sent with each read, so reading
+    /// through read_gc() keeps them running until set() turns them off.
+    pub struct GcRumble {
+        rumble: [u8; 4]
+    }
+
+    impl GcRumble {
+        /// Start with rumble off on every port.
+        pub fn new() -> GcRumble {
+            return GcRumble { rumble: [0; 4] };
+        }
+
+        /// Turn rumble on or off for a port, from the next read_gc() on.
+        pub fn set(&mut self, port: ControllerNum, on: bool) {
+            self.rumble[port as usize] = on as u8;
+        }
+
+        /// Return whether rumble is set for a port.
+        pub fn is_on(&self, port: ControllerNum) -> bool {
+            return self.rumble[port as usize] != 0;
+        }
+
+        /// Read the GameCube controllers, sending the stored rumble state.
+        pub fn read_gc(&self, data_out: &mut ControllerData) {
+            read_gc_controller_data(data_out, self.rumble);
+        }
+    }
+
     /// This returns the values set on power up, or the values the user requested
     /// by reseting the controller by holding X-Y-start. Apps should use these as
     /// the center stick values. The meaning of the two deadzone values is unknown.
@@ -363,12 +1119,76 @@ pub mod Controller {
         unsafe { return bindings::get_controllers_present(); }
     }
 
-    /// Queries the controller interface and returns a bitmask specifying
-    /// which controllers have recognized accessories present.
-    /// See CONTROLLER_1_INSERTED, CONTROLLER_2_INSERTED, CONTROLLER_3_INSERTED
-    /// and CONTROLLER_4_INSERTED.
-    pub fn get_accessories_present(data: &mut ControllerData) -> i32 {
-        unsafe { return bindings::get_accessories_present(data); }
+    const INSERTED_MASKS: [i32; 4] = [
+        crate::CONTROLLER_1_INSERTED!(),
+        crate::CONTROLLER_2_INSERTED!(),
+        crate::CONTROLLER_3_INSERTED!(),
+        crate::CONTROLLER_4_INSERTED!()
+    ];
+
+    /// Ports whose connection changed between two ConnectionTracker polls,
+    /// indexed by ControllerNum.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct ConnectionDelta {
+        pub inserted: [bool; 4],
+        pub removed: [bool; 4]
+    }
+
+    impl ConnectionDelta {
+        pub fn inserted(&self, num: ControllerNum) -> bool {
+            return self.inserted[num as usize];
+        }
+
+        pub fn removed(&self, num: ControllerNum) -> bool {
+            return self.removed[num as usize];
+        }
+
+        /// Return whether no controller was inserted or removed.
+        pub fn is_empty(&self) -> bool {
+            return self.inserted == [false; 4] && self.removed == [false; 4];
+        }
+    }
+
+    /// Detects controllers being plugged in or removed between polls of get_controllers_present().
+    pub struct ConnectionTracker {
+        present: i32
+    }
+
+    impl ConnectionTracker {
+        /// Start tracking from the controllers present now.
+        pub fn new() -> ConnectionTracker {
+            return ConnectionTracker { present: get_controllers_present() };
+        }
+
+        /// Return the ports that changed since new() or the previous poll().
+        pub fn poll(&mut self) -> ConnectionDelta {
+            let present = get_controllers_present();
+            let mut delta = ConnectionDelta { inserted: [false; 4], removed: [false; 4] };
+
+            for (port, &mask) in INSERTED_MASKS.iter().enumerate() {
+                let was = self.present & mask != 0;
+                let is = present & mask != 0;
+
+                delta.inserted[port] = is && !was;
+                delta.removed[port] = was && !is;
+            }
+
+            self.present = present;
+            return delta;
+        }
+
+        /// Return whether a controller was present at the last poll (or new()).
+        pub fn is_present(&self, num: ControllerNum) -> bool {
+            return self.present & INSERTED_MASKS[num as usize] != 0;
+        }
+    }
+
+    /// Queries the controller interface and returns a bitmask specifying
+    /// which controllers have recognized accessories present.
+    /// See CONTROLLER_1_INSERTED, CONTROLLER_2_INSERTED, CONTROLLER_3_INSERTED
+    /// and CONTROLLER_4_INSERTED.
+    pub fn get_accessories_present(data: &mut ControllerData) -> i32 {
+        unsafe { return bindings::get_accessories_present(data); }
     }
 
     /// Scan the four controller ports and calculate the buttons state.
@@ -407,26 +1227,37 @@ pub mod Controller {
 
     /// Return the direction of the DPAD specified in controller.
     /// Follows standard polar coordinates, where 0 = 0, pi/4 = 1,
-    /// pi/2 = 2, etc... Returns -1 when not pressed. Must be used in
-    /// conjunction with Controller::scan_controllers()
-    pub fn get_dpad_direction(controller: ControllerNum) -> DPadDirection {
-        unsafe {
-            return match bindings::get_dpad_direction(controller as i32) {
-                0 => DPadDirection::R,
-                1 => DPadDirection::UR,
-                2 => DPadDirection::U,
-                3 => DPadDirection::UL,
-                4 => DPadDirection::L,
-                5 => DPadDirection::DL,
-                6 => DPadDirection::D,
-                7 => DPadDirection::DR,
-                bad => panic!("Invalid result from Controller::get_dpad_direction(): {}", bad)
-            };
-        }
+    /// pi/2 = 2, etc... Returns None when not pressed (libdragon's -1),
+    /// which includes impossible combinations such as up and down at once.
+    /// Must be used in conjunction with Controller::scan_controllers()
+    pub fn get_dpad_direction(controller: ControllerNum) -> Option<DPadDirection> {
+        let direction = unsafe { bindings::get_dpad_direction(controller as i32) };
+
+        return match direction {
+            -1 => None,
+            _ => match DPadDirection::from_i32(direction) {
+                Some(direction) => Some(direction),
+                None => panic!("Invalid result from Controller::get_dpad_direction(): {}", direction)
+            }
+        };
+    }
+
+    // The low 5 bits of the address sent to the pak carry its CRC, so only
+    // whole 32-byte blocks can be addressed. An unaligned address would reach
+    // the block below it instead.
+    fn mempak_block_aligned(address: u16) -> bool {
+        return address % 32 == 0;
     }
 
     /// Given a controller and an address, read 32 bytes from a mempak and return them in data.
-    pub fn read_mempak_address(controller: ControllerNum, address: u16, data_out: &mut [u8]) -> MemPakResult {
+    ///
+    /// libdragon always transfers a whole 32-byte block, so the buffer is sized to match.
+    /// address must be a multiple of 32; otherwise OutOfRange is returned.
+    pub fn read_mempak_address(controller: ControllerNum, address: u16, data_out: &mut [u8; 32]) -> MemPakResult {
+        if !mempak_block_aligned(address) {
+            return MemPakResult::OutOfRange;
+        }
+
         unsafe {
             return match bindings::read_mempak_address(controller as i32, address, data_out.as_mut_ptr()) {
                 0 => MemPakResult::Success,
@@ -439,9 +1270,17 @@ pub mod Controller {
     }
 
     /// Given a controller and an address, write 32 bytes to a mempak from data.
-    pub fn write_mempak_address(controller: ControllerNum, address: u16, data_in: &mut [u8]) -> MemPakResult {
+    ///
+    /// libdragon always transfers a whole 32-byte block, so the buffer is sized to match.
+    /// address must be a multiple of 32; otherwise OutOfRange is returned.
+    pub fn write_mempak_address(controller: ControllerNum, address: u16, data_in: &[u8; 32]) -> MemPakResult {
+        if !mempak_block_aligned(address) {
+            return MemPakResult::OutOfRange;
+        }
+
         unsafe {
-            return match bindings::write_mempak_address(controller as i32, address, data_in.as_mut_ptr()) {
+            // libdragon only reads from data, despite the non-const pointer.
+            return match bindings::write_mempak_address(controller as i32, address, data_in.as_ptr() as *mut u8) {
                 0 => MemPakResult::Success,
                 -1 => MemPakResult::OutOfRange,
                 -2 => MemPakResult::NoMemPak,
@@ -453,13 +1292,21 @@ pub mod Controller {
 
     /// Given a controller, identify the particular accessory type inserted.
     pub fn identify_accessory(controller: ControllerNum) -> AccessoryType {
+        return match try_identify_accessory(controller) {
+            Ok(accessory) => accessory,
+            Err(UnexpectedCode(bad)) => panic!("Invalid result from Controller::identify_accessory(): {}", bad)
+        };
+    }
+
+    /// Like identify_accessory(), but return an unrecognized result instead of panicking.
+    pub fn try_identify_accessory(controller: ControllerNum) -> Result<AccessoryType, UnexpectedCode> {
         unsafe {
             return match bindings::identify_accessory(controller as i32) {
-                0 => AccessoryType::None,
-                1 => AccessoryType::MemPak,
-                2 => AccessoryType::RumblePak,
-                3 => AccessoryType::VRU,
-                bad => panic!("Invalid result from Controller::identify_accessory(): {}", bad)
+                0 => Ok(AccessoryType::None),
+                1 => Ok(AccessoryType::MemPak),
+                2 => Ok(AccessoryType::RumblePak),
+                3 => Ok(AccessoryType::VRU),
+                bad => Err(UnexpectedCode(bad))
             };
         }
     }
@@ -535,13 +1382,101 @@ pub mod Controller {
     pub fn eeprom_write(block: i32, data: &[u8; 8]) {
         unsafe { bindings::eeprom_write(block, data.as_ptr()); }
     }
+
+    /// Errors returned by eeprom_read_all() and eeprom_write_all().
+    #[derive(Debug)]
+    pub enum EepromError {
+        NotPresent, // The cartridge has no EEPROM
+        WrongSize   // The buffer isn't exactly eeprom_size() bytes
+    }
+
+    /// Size in bytes of the cartridge EEPROM: 512 for 4K, 2048 for 16K, 0 if there is none.
+    pub fn eeprom_size() -> usize {
+        return match eeprom_present() {
+            EEPROMType::None => 0,
+            EEPROMType::_4K => 64 * 8,
+            EEPROMType::_16K => 256 * 8
+        };
+    }
+
+    fn eeprom_check(len: usize) -> Result<(), EepromError> {
+        return match eeprom_size() {
+            0 => Err(EepromError::NotPresent),
+            size if size != len => Err(EepromError::WrongSize),
+            _ => Ok(())
+        };
+    }
+
+    /// Read the whole EEPROM into buf, which must be exactly eeprom_size() bytes.
+    pub fn eeprom_read_all(buf: &mut [u8]) -> Result<(), EepromError> {
+        eeprom_check(buf.len())?;
+
+        for (block, chunk) in buf.chunks_exact_mut(8).enumerate() {
+            chunk.copy_from_slice(&eeprom_read(block as i32));
+        }
+
+        return Ok(());
+    }
+
+    /// Write data, which must be exactly eeprom_size() bytes, over the whole EEPROM.
+    pub fn eeprom_write_all(data: &[u8]) -> Result<(), EepromError> {
+        eeprom_check(data.len())?;
+
+        for (block, chunk) in data.chunks_exact(8).enumerate() {
+            let mut bytes = [0; 8];
+            bytes.copy_from_slice(chunk);
+
+            eeprom_write(block as i32, &bytes);
+        }
+
+        return Ok(());
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn dpad_direction_from_i32() {
+            let expected = [
+                DPadDirection::R, DPadDirection::UR, DPadDirection::U, DPadDirection::UL,
+                DPadDirection::L, DPadDirection::DL, DPadDirection::D, DPadDirection::DR
+            ];
+
+            for (value, direction) in expected.iter().enumerate() {
+                assert_eq!(DPadDirection::from_i32(value as i32), Some(*direction));
+                assert_eq!(*direction as i32, value as i32);
+            }
+        }
+
+        #[test]
+        fn dpad_direction_from_i32_out_of_range() {
+            assert_eq!(DPadDirection::from_i32(-1), None);
+            assert_eq!(DPadDirection::from_i32(8), None);
+        }
+
+        #[test]
+        fn mempak_block_aligned_accepts_block_starts() {
+            assert!(mempak_block_aligned(0x0000));
+            assert!(mempak_block_aligned(0x0020));
+            assert!(mempak_block_aligned(0x7FE0));
+            assert!(mempak_block_aligned(0x8000));
+        }
+
+        #[test]
+        fn mempak_block_aligned_rejects_offsets_within_a_block() {
+            assert!(!mempak_block_aligned(0x0001));
+            assert!(!mempak_block_aligned(0x001F));
+            assert!(!mempak_block_aligned(0x7FF0));
+        }
+    }
 }
 
 /// Managed mempak interface
 pub mod MemoryPak {
     use cty::*;
 
-    use crate::{Controller::ControllerNum, bindings};
+    use crate::{Controller::ControllerNum, UnexpectedCode, bindings};
 
     #[repr(C)]
     pub struct EntryStructure {
@@ -555,27 +1490,110 @@ pub mod MemoryPak {
         pub name: [c_char; 19]
     }
 
+    impl EntryStructure {
+        /// The note name and extension, e.g. "GAME NAME.A", cut at the terminating NUL.
+        ///
+        /// get_entry() already decodes the name from the N64 character set, so
+        /// this only trims it; any invalid UTF-8 ends the name early.
+        pub fn decoded_name(&self) -> &str {
+            let name = unsafe { core::slice::from_raw_parts(self.name.as_ptr().cast::<u8>(), self.name.len()) };
+            let len = name.iter().position(|&c| c == 0).unwrap_or(name.len());
+
+            return match core::str::from_utf8(&name[..len]) {
+                Ok(name) => name,
+                Err(err) => unsafe { core::str::from_utf8_unchecked(&name[..err.valid_up_to()]) }
+            };
+        }
+    }
+
+    /// Decode a character of a note name as stored on the mempak, using libdragon's table.
+    ///
+    /// Only needed for names read straight from the note table with
+    /// read_mempak_address(); unprintable codes decode to a space and 0x00 to '\0'.
+    pub fn decode_note_char(c: u8) -> char {
+        return match c {
+            0x00 => '\0',
+            0x0F => ' ',
+            0x10..=0x19 => (b'0' + (c - 0x10)) as char,
+            0x1A..=0x33 => (b'A' + (c - 0x1A)) as char,
+            0x34 => '!',
+            0x35 => '"',
+            0x36 => '#',
+            0x37 => '`',
+            0x38 => '*',
+            0x39 => '+',
+            0x3A => ',',
+            0x3B => '-',
+            0x3C => '.',
+            0x3D => '/',
+            0x3E => ':',
+            0x3F => '=',
+            0x40 => '?',
+            0x41 => '@',
+            _ => ' '
+        };
+    }
+
     #[repr(i32)]
+    #[derive(Debug)]
     pub enum DeleteEntryResult {
         DeletedSuccessfully = 0,
         InvalidEntry = -1,
         BadMemPak = -2 // Or mempak isn't present
     }
 
+    impl core::fmt::Display for DeleteEntryResult {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                DeleteEntryResult::DeletedSuccessfully => "deleted successfully",
+                DeleteEntryResult::InvalidEntry => "invalid entry",
+                DeleteEntryResult::BadMemPak => "mempak missing or bad"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[repr(i32)]
+    #[derive(Debug)]
     pub enum FormatResult {
         FormattedSuccessfully = 0,
         BadMemPak = -2 // Or not present
     }
 
+    impl core::fmt::Display for FormatResult {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                FormatResult::FormattedSuccessfully => "formatted successfully",
+                FormatResult::BadMemPak => "mempak missing or bad"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[repr(i32)]
+    #[derive(Debug)]
     pub enum GetEntryResult {
         ReadSuccessfully = 0,
         BadEntry = -1, // Out of bounds or entry_data is null
         BadMemPak = -2, // Or isn't present
     }
 
+    impl core::fmt::Display for GetEntryResult {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                GetEntryResult::ReadSuccessfully => "read successfully",
+                GetEntryResult::BadEntry => "entry out of bounds or invalid",
+                GetEntryResult::BadMemPak => "mempak missing or bad"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[repr(i32)]
+    #[derive(Debug)]
     pub enum ReadEntryDataResult {
         ReadSuccessfully = 0,
         BadEntry = -1, // Out of bounds or corrupted
@@ -583,21 +1601,61 @@ pub mod MemoryPak {
         DataUnreadable = -3
     }
 
+    impl core::fmt::Display for ReadEntryDataResult {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                ReadEntryDataResult::ReadSuccessfully => "read successfully",
+                ReadEntryDataResult::BadEntry => "entry out of bounds or corrupted",
+                ReadEntryDataResult::BadMemPak => "mempak missing or bad",
+                ReadEntryDataResult::DataUnreadable => "entry data unreadable"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[repr(i32)]
+    #[derive(Debug)]
     pub enum ReadSectorResult {
         ReadSuccessfully = 0,
         BadSector = -1, // Sector out of bounds or sector_data is null
         ErrorRead = -2 // Error reading part of a sector
     }
 
+    impl core::fmt::Display for ReadSectorResult {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                ReadSectorResult::ReadSuccessfully => "read successfully",
+                ReadSectorResult::BadSector => "sector out of bounds",
+                ReadSectorResult::ErrorRead => "error reading sector"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[repr(i32)]
+    #[derive(Debug)]
     pub enum ValidateResult {
         Valid = 0,
         NotPresent = -2, // Or couldn't be read
         BadMemPak = -3 // Or unformatted
     }
 
+    impl core::fmt::Display for ValidateResult {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                ValidateResult::Valid => "valid",
+                ValidateResult::NotPresent => "mempak missing or unreadable",
+                ValidateResult::BadMemPak => "mempak bad or unformatted"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[repr(i32)]
+    #[derive(Debug)]
     pub enum WriteEntryDataResult {
         WrittenSuccessfully = 0,
         InvalidParameter = -1, // Or note has no length
@@ -607,13 +1665,41 @@ pub mod MemoryPak {
         TOCFull = -5, // Not enough room in the TOC to add a new entry
     }
 
+    impl core::fmt::Display for WriteEntryDataResult {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                WriteEntryDataResult::WrittenSuccessfully => "written successfully",
+                WriteEntryDataResult::InvalidParameter => "invalid parameter or empty note",
+                WriteEntryDataResult::BadMemPak => "mempak missing or bad",
+                WriteEntryDataResult::WriteError => "error writing entry",
+                WriteEntryDataResult::NotEnoughSpace => "not enough space on mempak",
+                WriteEntryDataResult::TOCFull => "no room left in the table of contents"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[repr(i32)]
+    #[derive(Debug)]
     pub enum WriteSectorResult {
         WrittenSuccessfully = 0,
         BadSector = -1, // Out of bounds or sector_data is null
         WriteError = -2
     }
 
+    impl core::fmt::Display for WriteSectorResult {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                WriteSectorResult::WrittenSuccessfully => "written successfully",
+                WriteSectorResult::BadSector => "sector out of bounds",
+                WriteSectorResult::WriteError => "error writing sector"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[macro_export] macro_rules! MEMPAK_BLOCK_SIZE {() => (256)}  // Size in bytes of a mempak block
     #[macro_export] macro_rules! BLOCK_EMPTY {() => (0x03)}       // Block is empty
     #[macro_export] macro_rules! BLOCK_LAST {() => (0x01)}        // Last block in the note
@@ -646,12 +1732,20 @@ pub mod MemoryPak {
 
     /// This function will return whether the mempak in a particular controller is formatted and valid.
     pub fn validate_mempak(controller: ControllerNum) -> ValidateResult {
+        return match try_validate_mempak(controller) {
+            Ok(result) => result,
+            Err(UnexpectedCode(bad)) => panic!("Invalid result from MemPak::validate_mempak(): {}", bad)
+        };
+    }
+
+    /// Like validate_mempak(), but return an unrecognized result instead of panicking.
+    pub fn try_validate_mempak(controller: ControllerNum) -> Result<ValidateResult, UnexpectedCode> {
         unsafe {
             return match bindings::validate_mempak(controller as i32) {
-                0 => ValidateResult::Valid,
-                -2 => ValidateResult::NotPresent,
-                -3 => ValidateResult::BadMemPak,
-                bad => panic!("Invalid result from MemPak::validate_mempak(): {}", bad)
+                0 => Ok(ValidateResult::Valid),
+                -2 => Ok(ValidateResult::NotPresent),
+                -3 => Ok(ValidateResult::BadMemPak),
+                bad => Err(UnexpectedCode(bad))
             };
         }
     }
@@ -756,7 +1850,7 @@ pub mod MemoryPak {
 pub mod TransferPak {
     use cty::*;
 
-    use crate::{Controller::ControllerNum, bindings};
+    use crate::{Controller::ControllerNum, UnexpectedCode, bindings};
 
     #[repr(C)]
     pub struct GameboyCartridgeHeader {
@@ -776,20 +1870,101 @@ pub mod TransferPak {
         pub overflow: [uint8_t; 16]
     }
 
+    /// Memory bank controller, decoded from the header cartridge_type.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum MbcType {
+        RomOnly,      // 0x00, 0x08-0x09 (ROM, optionally with RAM)
+        Mbc1,         // 0x01-0x03
+        Mbc2,         // 0x05-0x06
+        Mmm01,        // 0x0B-0x0D
+        Mbc3,         // 0x0F-0x13
+        Mbc5,         // 0x19-0x1E
+        Mbc6,         // 0x20
+        Mbc7,         // 0x22
+        PocketCamera, // 0xFC
+        Tama5,        // 0xFD
+        HuC3,         // 0xFE
+        HuC1,         // 0xFF
+        Unknown(u8)   // Any other cartridge_type
+    }
+
+    impl GameboyCartridgeHeader {
+        /// ROM size in bytes, or None for an unknown rom_size_code.
+        pub fn rom_size_bytes(&self) -> Option<usize> {
+            return rom_size_from_code(self.rom_size_code);
+        }
+
+        /// External RAM size in bytes, or None for an unknown ram_size_code.
+        ///
+        /// MBC2 carts report 0 here; their 512 half-byte RAM is built into the MBC.
+        pub fn ram_size_bytes(&self) -> Option<usize> {
+            return match self.ram_size_code {
+                0 => Some(0),
+                1 => Some(2 * 1024),
+                2 => Some(8 * 1024),
+                3 => Some(32 * 1024),
+                4 => Some(128 * 1024),
+                5 => Some(64 * 1024),
+                _ => None
+            };
+        }
+
+        /// The memory bank controller on the cartridge.
+        pub fn mbc_type(&self) -> MbcType {
+            return match self.cartridge_type {
+                0x00 | 0x08..=0x09 => MbcType::RomOnly,
+                0x01..=0x03 => MbcType::Mbc1,
+                0x05..=0x06 => MbcType::Mbc2,
+                0x0B..=0x0D => MbcType::Mmm01,
+                0x0F..=0x13 => MbcType::Mbc3,
+                0x19..=0x1E => MbcType::Mbc5,
+                0x20 => MbcType::Mbc6,
+                0x22 => MbcType::Mbc7,
+                0xFC => MbcType::PocketCamera,
+                0xFD => MbcType::Tama5,
+                0xFE => MbcType::HuC3,
+                0xFF => MbcType::HuC1,
+                other => MbcType::Unknown(other)
+            };
+        }
+
+        /// The title, without padding.
+        ///
+        /// When the last title byte is a GBC flag (0x80 or 0xC0) it is left out,
+        /// so newer carts include their manufacturer code in the title. The title
+        /// ends at the first NUL or non-ASCII byte, and trailing spaces are trimmed.
+        pub fn title_str(&self) -> &str {
+            let title = unsafe { &self.unnamed_1.title };
+            let title = match title[15] {
+                0x80 | 0xC0 => &title[..15],
+                _ => &title[..]
+            };
+
+            let len = title.iter().position(|&c| c == 0 || !c.is_ascii()).unwrap_or(title.len());
+
+            // Only ASCII bytes are kept, so this is valid UTF-8.
+            return unsafe { core::str::from_utf8_unchecked(&title[..len]) }.trim_end_matches(' ');
+        }
+    }
+
+    /// The 16 title bytes, laid out as in C: a union, so the header keeps its byte layout.
     #[repr(C)]
-    pub enum GBCTitle {
-        title([uint8_t; 16]),
-        old_title(OldTitle),
-        new_title(NewTitle)
+    #[derive(Clone, Copy)]
+    pub union GBCTitle {
+        pub title: [uint8_t; 16],
+        pub old_title: OldTitle,
+        pub new_title: NewTitle
     }
 
     #[repr(C)]
+    #[derive(Clone, Copy)]
     pub struct OldTitle {
         pub title: [uint8_t; 15],
         pub gbc_support: GBCSupportType
     }
 
     #[repr(C)]
+    #[derive(Clone, Copy)]
     pub struct NewTitle {
         pub title: [uint8_t; 11],
         pub manufacturer_code: [uint8_t; 4],
@@ -797,6 +1972,7 @@ pub mod TransferPak {
     }
 
     #[repr(u8)]
+    #[derive(Clone, Copy)]
     pub enum GBCSupportType {
         GBC_NOT_SUPPORTED = 0x00,
         GBC_DMG_SUPPORTED = 0x80,
@@ -804,6 +1980,7 @@ pub mod TransferPak {
     }
 
     #[repr(i32)]
+    #[derive(Debug)]
     pub enum TPakError {
         Success = 0,
         InvalidArgument = -1,
@@ -814,6 +1991,22 @@ pub mod TransferPak {
         AddressOverflow = -6
     }
 
+    impl core::fmt::Display for TPakError {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                TPakError::Success => "success",
+                TPakError::InvalidArgument => "invalid argument",
+                TPakError::NoTPak => "no transfer pak present",
+                TPakError::NoController => "no controller present",
+                TPakError::UnknownBehaviour => "transfer pak behaved unexpectedly",
+                TPakError::NoCartridge => "no cartridge in transfer pak",
+                TPakError::AddressOverflow => "address out of range"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[repr(u8)]
     pub enum TPakStatus {
         Ready = 0x01,
@@ -823,21 +2016,51 @@ pub mod TransferPak {
         Powered = 0x80
     }
 
+    /// A transfer pak status byte decoded into its TPakStatus flags.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Status {
+        pub ready: bool,
+        pub was_reset: bool,
+        pub is_resetting: bool,
+        pub removed: bool,
+        pub powered: bool
+    }
+
+    impl Status {
+        pub const fn from_bits(bits: u8) -> Status {
+            return Status {
+                ready: bits & TPakStatus::Ready as u8 != 0,
+                was_reset: bits & TPakStatus::WasReset as u8 != 0,
+                is_resetting: bits & TPakStatus::IsResetting as u8 != 0,
+                removed: bits & TPakStatus::Removed as u8 != 0,
+                powered: bits & TPakStatus::Powered as u8 != 0
+            };
+        }
+    }
+
     /// Prepare transfer pak for I/O.
     ///
     /// Powers on the transfer pak and sets access mode to allow I/O to gameboy cartridge.
     /// Will also perform a series of checks to confirm transfer pak can be accessed reliably.
     pub fn init(controller: ControllerNum) -> TPakError {
+        return match try_init(controller) {
+            Ok(result) => result,
+            Err(UnexpectedCode(bad)) => panic!("Invalid result from TransferPak::init(): {}", bad)
+        };
+    }
+
+    /// Like init(), but return an unrecognized result instead of panicking.
+    pub fn try_init(controller: ControllerNum) -> Result<TPakError, UnexpectedCode> {
         unsafe {
             return match bindings::tpak_init(controller as i32) {
-                0 => TPakError::Success,
-                -1 => TPakError::InvalidArgument,
-                -2 => TPakError::NoTPak,
-                -3 => TPakError::NoController,
-                -4 => TPakError::UnknownBehaviour,
-                -5 => TPakError::NoCartridge,
-                -6 => TPakError::AddressOverflow,
-                bad => panic!("Invalid result from TransferPak::init(): {}", bad)
+                0 => Ok(TPakError::Success),
+                -1 => Ok(TPakError::InvalidArgument),
+                -2 => Ok(TPakError::NoTPak),
+                -3 => Ok(TPakError::NoController),
+                -4 => Ok(TPakError::UnknownBehaviour),
+                -5 => Ok(TPakError::NoCartridge),
+                -6 => Ok(TPakError::AddressOverflow),
+                bad => Err(UnexpectedCode(bad))
             };
         }
     }
@@ -926,6 +2149,11 @@ pub mod TransferPak {
         }
     }
 
+    /// Like get_status(), but decode the flags.
+    pub fn get_status_flags(controller: ControllerNum) -> Status {
+        return Status::from_bits(get_status(controller));
+    }
+
     /// Reads a gameboy cartridge header in to memory.
     pub fn get_cartridge_header(controller: ControllerNum, header_out: &mut GameboyCartridgeHeader) -> TPakError {
         unsafe {
@@ -985,13 +2213,156 @@ pub mod TransferPak {
             };
         }
     }
+
+    // Largest transfer handed to tpak_read()/tpak_write() at once by the slice functions.
+    const SLICE_CHUNK: usize = 0x8000;
+
+    fn check_span(address: u16, len: usize) -> TPakError {
+        return match address as usize + len > 0x10000 {
+            true => TPakError::AddressOverflow,
+            false => TPakError::Success
+        };
+    }
+
+    /// Read data from a gameboy cartridge into a slice, taking the size from the slice.
+    ///
+    /// Returns AddressOverflow without touching the cartridge if the slice runs
+    /// past 0xFFFF. As with read(), address and length should be multiples of 32.
+    pub fn read_slice(controller: ControllerNum, address: u16, buffer_out: &mut [u8]) -> TPakError {
+        if let TPakError::AddressOverflow = check_span(address, buffer_out.len()) {
+            return TPakError::AddressOverflow;
+        }
+
+        for (index, chunk) in buffer_out.chunks_mut(SLICE_CHUNK).enumerate() {
+            let chunk_address = address as usize + index * SLICE_CHUNK;
+
+            match read(controller, chunk_address as u16, chunk, chunk.len() as u16) {
+                TPakError::Success => {},
+                error => return error
+            }
+        }
+
+        return TPakError::Success;
+    }
+
+    /// Write data from a slice to a gameboy cartridge, taking the size from the slice.
+    ///
+    /// Bounds and alignment are as for read_slice(). See write() for bank switching.
+    pub fn write_slice(controller: ControllerNum, address: u16, data_in: &[u8]) -> TPakError {
+        if let TPakError::AddressOverflow = check_span(address, data_in.len()) {
+            return TPakError::AddressOverflow;
+        }
+
+        for (index, chunk) in data_in.chunks(SLICE_CHUNK).enumerate() {
+            let chunk_address = address as usize + index * SLICE_CHUNK;
+
+            unsafe {
+                // tpak_write() only reads from the buffer.
+                let result = bindings::tpak_write(controller as i32, chunk_address as u16, chunk.as_ptr() as *mut u8, chunk.len() as u16);
+
+                match result {
+                    0 => {},
+                    -1 => return TPakError::InvalidArgument,
+                    -2 => return TPakError::NoTPak,
+                    -3 => return TPakError::NoController,
+                    -4 => return TPakError::UnknownBehaviour,
+                    -5 => return TPakError::NoCartridge,
+                    -6 => return TPakError::AddressOverflow,
+                    bad => panic!("Invalid result from TransferPak::write_slice(): {}", bad)
+                }
+            }
+        }
+
+        return TPakError::Success;
+    }
+
+    /// Size of a gameboy ROM bank, and of the switchable window at 0x4000.
+    pub const ROM_BANK_SIZE: usize = 0x4000;
+
+    /// ROM size in bytes for a header rom_size_code, or None for an unknown code.
+    pub fn rom_size_from_code(rom_size_code: u8) -> Option<usize> {
+        return match rom_size_code {
+            0..=8 => Some(0x8000 << rom_size_code),
+            _ => None
+        };
+    }
+
+    // Register writes selecting a ROM bank into the window at 0x4000: the low
+    // bits go to 0x2000, then the upper bits to 0x4000 (MBC1) or, for the 8MB
+    // size only MBC5 supports, bit 8 to 0x3000. 0x3000 can't be used below
+    // that, since on MBC1 it is still the low bank register.
+    fn bank_select(rom_size_code: u8, bank: usize) -> [(u16, u8); 2] {
+        let upper = match rom_size_code {
+            8 => (0x3000, (bank >> 8) as u8),
+            _ => (0x4000, (bank >> 5) as u8)
+        };
+
+        return [(0x2000, bank as u8), upper];
+    }
+
+    /// Read a whole cartridge ROM, switching banks as needed.
+    ///
+    /// rom_size_code comes from the cartridge header and buffer_out must hold at
+    /// least the ROM size it decodes to; otherwise InvalidArgument is returned.
+    /// Banks are selected by writing the bank number to 0x2000 and its upper bits
+    /// to 0x4000, which covers MBC1, MBC3 and MBC5 carts up to 4MB; 8MB carts
+    /// (MBC5 only) get bit 8 of the bank written to 0x3000 instead. As on real
+    /// hardware, MBC1 banks 0x20, 0x40 and 0x60 read back as the bank after them.
+    pub fn read_rom(controller: ControllerNum, rom_size_code: u8, buffer_out: &mut [u8]) -> TPakError {
+        let rom_size = match rom_size_from_code(rom_size_code) {
+            Some(size) if size <= buffer_out.len() => size,
+            _ => return TPakError::InvalidArgument
+        };
+
+        for (bank, chunk) in buffer_out[..rom_size].chunks_mut(ROM_BANK_SIZE).enumerate() {
+            let result = match bank {
+                0 => read_slice(controller, 0x0000, chunk),
+                _ => {
+                    let [(low_address, low), (high_address, high)] = bank_select(rom_size_code, bank);
+
+                    match set_value(controller, low_address, low) {
+                        TPakError::Success => match set_value(controller, high_address, high) {
+                            TPakError::Success => read_slice(controller, ROM_BANK_SIZE as u16, chunk),
+                            error => error
+                        },
+                        error => error
+                    }
+                }
+            };
+
+            match result {
+                TPakError::Success => {},
+                error => return error
+            }
+        }
+
+        return TPakError::Success;
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn bank_select_splits_mbc1_bank() {
+            assert_eq!(bank_select(6, 0x45), [(0x2000, 0x45), (0x4000, 0x02)]);
+        }
+
+        #[test]
+        fn bank_select_uses_mbc5_bit_8_for_8mb() {
+            assert_eq!(bank_select(8, 0x1FF), [(0x2000, 0xFF), (0x3000, 0x01)]);
+            assert_eq!(bank_select(8, 0x0FF), [(0x2000, 0xFF), (0x3000, 0x00)]);
+        }
+    }
 }
 
 /// Display Subsystem
 pub mod Display {
+    use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
     use cty::*;
 
-    use crate::bindings;
+    use crate::{Interrupt::{self, InterruptState}, N64System, Timer, bindings};
 
     #[repr(C)]
     pub enum Resolution {
@@ -1004,6 +2375,7 @@ pub mod Display {
     }
 
     #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub enum BitDepth {
         DEPTH_16_BPP,
         DEPTH_32_BPP
@@ -1026,12 +2398,119 @@ pub mod Display {
 
     pub type DisplayContext = c_int;
 
+    static BIT_DEPTH: AtomicU32 = AtomicU32::new(BitDepth::DEPTH_16_BPP as u32);
+
+    // Geometry passed to the last init(); zero buffers means not initialized.
+    static WIDTH: AtomicU32 = AtomicU32::new(0);
+    static HEIGHT: AtomicU32 = AtomicU32::new(0);
+    static BUFFERS: AtomicU32 = AtomicU32::new(0);
+
     /// Initialize video system. This sets up a double or triple buffered drawing surface
     /// which can be blitted or rendered to using software or hardware.
     pub fn init(res: Resolution, bitdepth: BitDepth, no_buffers: u32, gamma: Gamma, aa: AntiAlias) {
+        let (width, height) = match res {
+            Resolution::RESOLUTION_320x240 => (320, 240),
+            Resolution::RESOLUTION_640x480 => (640, 480),
+            Resolution::RESOLUTION_256x240 => (256, 240),
+            Resolution::RESOLUTION_512x480 => (512, 480),
+            Resolution::RESOLUTION_512x240 => (512, 240),
+            Resolution::RESOLUTION_640x240 => (640, 240)
+        };
+
+        BIT_DEPTH.store(bitdepth as u32, Ordering::Relaxed);
+        WIDTH.store(width, Ordering::Relaxed);
+        HEIGHT.store(height, Ordering::Relaxed);
+        BUFFERS.store(no_buffers, Ordering::Relaxed);
+
         unsafe { bindings::display_init(res, bitdepth, no_buffers, gamma, aa); }
     }
 
+    /// Named-argument alternative to init().
+    ///
+    /// Defaults to 320x240, 16bpp, double buffered, no gamma correction and no
+    /// anti-aliasing; override any of them before calling init().
+    pub struct Builder {
+        res: Resolution,
+        bitdepth: BitDepth,
+        buffers: u32,
+        gamma: Gamma,
+        aa: AntiAlias
+    }
+
+    impl Builder {
+        pub fn new() -> Builder {
+            return Builder {
+                res: Resolution::RESOLUTION_320x240,
+                bitdepth: BitDepth::DEPTH_16_BPP,
+                buffers: 2,
+                gamma: Gamma::GAMMA_NONE,
+                aa: AntiAlias::ANTIALIAS_OFF
+            };
+        }
+
+        pub fn resolution(mut self, res: Resolution) -> Builder {
+            self.res = res;
+            return self;
+        }
+
+        pub fn bit_depth(mut self, bitdepth: BitDepth) -> Builder {
+            self.bitdepth = bitdepth;
+            return self;
+        }
+
+        pub fn buffers(mut self, buffers: u32) -> Builder {
+            self.buffers = buffers;
+            return self;
+        }
+
+        pub fn gamma(mut self, gamma: Gamma) -> Builder {
+            self.gamma = gamma;
+            return self;
+        }
+
+        pub fn antialias(mut self, aa: AntiAlias) -> Builder {
+            self.aa = aa;
+            return self;
+        }
+
+        /// Initialize the video system with these settings. See init().
+        pub fn init(self) {
+            init(self.res, self.bitdepth, self.buffers, self.gamma, self.aa);
+        }
+    }
+
+    /// Return whether init() has been called without a later close().
+    pub fn is_initialized() -> bool {
+        return BUFFERS.load(Ordering::Relaxed) != 0;
+    }
+
+    /// Width in pixels of the resolution passed to the last init(), or 0.
+    pub fn width() -> u32 {
+        return WIDTH.load(Ordering::Relaxed);
+    }
+
+    /// Height in pixels of the resolution passed to the last init(), or 0.
+    pub fn height() -> u32 {
+        return HEIGHT.load(Ordering::Relaxed);
+    }
+
+    /// Number of buffers passed to the last init(), or 0.
+    ///
+    /// Display contexts are numbered 1 through this count.
+    pub fn buffer_count() -> u32 {
+        return BUFFERS.load(Ordering::Relaxed);
+    }
+
+    /// Return the bit depth passed to the last init().
+    ///
+    /// Before the display has been initialized this returns DEPTH_16_BPP.
+    pub fn bit_depth() -> BitDepth {
+        return match BIT_DEPTH.load(Ordering::Relaxed) {
+            1 => BitDepth::DEPTH_32_BPP,
+            _ => BitDepth::DEPTH_16_BPP
+        };
+    }
+
     /// Grab a display context that is safe for drawing. If none is available then this
     /// will return 0. Do not check out more than one display context at a time.
     pub fn lock() -> DisplayContext {
@@ -1044,33 +2523,269 @@ pub mod Display {
         unsafe { bindings::display_show(disp); }
     }
 
-    /// Close a display and free buffer memory associated with it.
-    pub fn close() {
-        unsafe { bindings::display_close(); }
+    /// A locked display context that is shown when dropped.
+    ///
+    /// Derefs to the raw DisplayContext for passing to the graphics and RDP
+    /// functions. Since showing consumes the guard, a context is shown exactly once.
+    pub struct LockedContext {
+        disp: DisplayContext
     }
-}
 
-/// DMA functionality for transfers between cartridge space and RDRAM.
-///
-/// The DMA controller is responsible for handling block and word accesses from
-/// the cartridge domain. Because of the nature of the catridge interface, code
-/// cannot use memcpy or standard pointer accesses on memory mapped to the catridge.
-/// Consequently, the peripheral interface (PI) provides a DMA controller for accessing data.
-///
-/// The DMA controller requires no initialization. Using dma_read() and dma_write() will
-/// allow reading from the cartridge and writing to the cartridge respectively in block
-/// mode. io_read() and io_write() will allow a single 32-bit integer to be read from
-/// or written to the cartridge. These are especially useful for manipulating registers
-/// on a cartridge such as a gameshark. Code should never make raw 32-bit reads or writes
-/// in the cartridge domain as it could collide with an in-progress DMA transfer or run
-/// into caching issues.
-pub mod DMA {
-    use cty::c_void;
-    use volatile::Volatile;
+    impl LockedContext {
+        /// Show the context now rather than at the end of the scope.
+        pub fn show_now(self) {
+            // Drop does the showing.
+        }
+    }
 
-    use crate::bindings;
+    impl core::ops::Deref for LockedContext {
+        type Target = DisplayContext;
 
-    /// Write to a peripheral.
+        fn deref(&self) -> &DisplayContext {
+            return &self.disp;
+        }
+    }
+
+    impl Drop for LockedContext {
+        fn drop(&mut self) {
+            show(self.disp);
+        }
+    }
+
+    /// Like lock(), but return a guard that shows the context when dropped,
+    /// or None if no context is available.
+    pub fn lock_guarded() -> Option<LockedContext> {
+        return match lock() {
+            0 => None,
+            disp => Some(LockedContext { disp })
+        };
+    }
+
+    /// Same as lock_guarded(): a guard for a free context, or None if none is free.
+    pub fn try_lock() -> Option<LockedContext> {
+        return lock_guarded();
+    }
+
+    /// Wait until a context is free and return a guard for it. See lock_blocking_with_wait().
+    pub fn lock_blocking() -> LockedContext {
+        return lock_blocking_with_wait(0);
+    }
+
+    /// Wait until a context is free, sleeping wait_ms between attempts, and
+    /// return a guard for it.
+    ///
+    /// Shown buffers only become free again from the VI interrupt, so the
+    /// display must be initialized and interrupts enabled, otherwise this would
+    /// never return; it panics instead.
+    pub fn lock_blocking_with_wait(wait_ms: u32) -> LockedContext {
+        if !is_initialized() {
+            panic!("Display::lock_blocking() requires the display to be initialized");
+        }
+
+        match Interrupt::get_interrupts_state() {
+            InterruptState::INTERRUPTS_ENABLED => {},
+            _ => panic!("Display::lock_blocking() requires interrupts to be enabled")
+        }
+
+        loop {
+            if let Some(locked) = lock_guarded() {
+                return locked;
+            }
+
+            if wait_ms > 0 {
+                N64System::wait_ms(wait_ms);
+            }
+        }
+    }
+
+    /// Close a display and free buffer memory associated with it.
+    pub fn close() {
+        BUFFERS.store(0, Ordering::Relaxed);
+
+        unsafe { bindings::display_close(); }
+    }
+
+    // Vertical blank count, split in two words as there is no 64-bit atomic.
+    static VSYNC_LOW: AtomicU32 = AtomicU32::new(0);
+    static VSYNC_HIGH: AtomicU32 = AtomicU32::new(0);
+    static VSYNC_HOOKED: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn count_vsync() {
+        if VSYNC_LOW.fetch_add(1, Ordering::Release) == u32::MAX {
+            VSYNC_HIGH.fetch_add(1, Ordering::Release);
+        }
+    }
+
+    fn hook_vsync() {
+        if !VSYNC_HOOKED.swap(true, Ordering::Relaxed) {
+            Interrupt::register_VI_handler(count_vsync);
+        }
+    }
+
+    /// Number of vertical blanks since the first call to vsync_count() or wait_vsync().
+    ///
+    /// The first call installs a VI handler to do the counting, so this needs
+    /// interrupts initialized (see Interrupt::init()) to advance, as well as
+    /// the VI interrupt that init() turns on.
+    pub fn vsync_count() -> u64 {
+        hook_vsync();
+
+        // The handler can run between the two reads; retry if the high word moved.
+        loop {
+            let high = VSYNC_HIGH.load(Ordering::Acquire);
+            let low = VSYNC_LOW.load(Ordering::Acquire);
+
+            if VSYNC_HIGH.load(Ordering::Acquire) == high {
+                return ((high as u64) << 32) | low as u64;
+            }
+        }
+    }
+
+    /// Block until the next vertical blank.
+    ///
+    /// Use this before show() or drawing to the front buffer to avoid tearing
+    /// when not going through RDP::RdpFrame. Interrupts must be initialized and
+    /// enabled, otherwise this would never return; it panics instead.
+    pub fn wait_vsync() {
+        match Interrupt::get_interrupts_state() {
+            InterruptState::INTERRUPTS_ENABLED => {},
+            _ => panic!("Display::wait_vsync() requires interrupts to be enabled")
+        }
+
+        hook_vsync();
+
+        let start = VSYNC_LOW.load(Ordering::Acquire);
+        while VSYNC_LOW.load(Ordering::Acquire) == start {}
+    }
+
+    /// Frame pacing on the VI interrupt, sharing its counter with vsync_count() and wait_vsync().
+    ///
+    /// The counting handler is registered on first use and stays registered
+    /// until disable() is called; using VSync again afterwards registers it anew.
+    pub struct VSync;
+
+    impl VSync {
+        /// Block until the next vertical blank. See wait_vsync().
+        pub fn wait_vblank() {
+            wait_vsync();
+        }
+
+        /// Number of vertical blanks counted so far. See vsync_count().
+        pub fn frame_count() -> u64 {
+            return vsync_count();
+        }
+
+        /// Unregister the counting handler. The count is kept, but stops advancing.
+        pub fn disable() {
+            if VSYNC_HOOKED.swap(false, Ordering::Relaxed) {
+                Interrupt::unregister_VI_handler(count_vsync);
+            }
+        }
+    }
+
+    /// Frame rate over the last N frames, from the 64-bit timer ticks.
+    ///
+    /// Call tick() once per presented frame, e.g. right after show(). Requires
+    /// the timer subsystem to be initialized.
+    pub struct FpsCounter<const N: usize> {
+        stamps: [i64; N],
+        next: usize,
+        count: usize
+    }
+
+    impl<const N: usize> FpsCounter<N> {
+        pub fn new() -> FpsCounter<N> {
+            assert!(N >= 2, "Display::FpsCounter needs a window of at least two frames");
+
+            return FpsCounter { stamps: [0; N], next: 0, count: 0 };
+        }
+
+        /// Record that a frame was presented now.
+        pub fn tick(&mut self) {
+            self.stamps[self.next] = Timer::ticks();
+            self.next = (self.next + 1) % N;
+            if self.count < N {
+                self.count += 1;
+            }
+        }
+
+        /// Average frames per second over the recorded window, or 0 until two
+        /// frames have been recorded.
+        pub fn fps(&self) -> f32 {
+            if self.count < 2 {
+                return 0.0;
+            }
+
+            let newest = self.stamps[(self.next + N - 1) % N];
+            let oldest = self.stamps[(self.next + N - self.count) % N];
+            let micros = Timer::TIMER_MICROS_LL(newest - oldest);
+
+            if micros <= 0 {
+                return 0.0;
+            }
+
+            return (self.count - 1) as f32 * 1_000_000.0 / micros as f32;
+        }
+
+        /// Forget the recorded frames, e.g. after a loading screen.
+        pub fn reset(&mut self) {
+            self.count = 0;
+        }
+    }
+}
+
+/// DMA functionality for transfers between cartridge space and RDRAM.
+///
+/// The DMA controller is responsible for handling block and word accesses from
+/// the cartridge domain. Because of the nature of the catridge interface, code
+/// cannot use memcpy or standard pointer accesses on memory mapped to the catridge.
+/// Consequently, the peripheral interface (PI) provides a DMA controller for accessing data.
+///
+/// The DMA controller requires no initialization. Using dma_read() and dma_write() will
+/// allow reading from the cartridge and writing to the cartridge respectively in block
+/// mode. io_read() and io_write() will allow a single 32-bit integer to be read from
+/// or written to the cartridge. These are especially useful for manipulating registers
+/// on a cartridge such as a gameshark. Code should never make raw 32-bit reads or writes
+/// in the cartridge domain as it could collide with an in-progress DMA transfer or run
+/// into caching issues.
+pub mod DMA {
+    use cty::{c_ulong, c_void};
+    use volatile::Volatile;
+
+    use crate::{N64System, bindings};
+
+    /// Errors returned by the slice transfers.
+    #[derive(Debug)]
+    pub enum DmaError {
+        Empty,           // The slice has no bytes to transfer
+        UnalignedRam,    // The slice doesn't start on an 8-byte boundary
+        UnalignedPi,     // The cartridge address isn't a multiple of 2
+        OddLength        // The slice length isn't a multiple of 2
+    }
+
+    // The PI moves halfwords from a halfword-aligned cartridge address, and
+    // needs 8-byte aligned RDRAM to transfer reliably.
+    fn check_dma(ptr: *const u8, len: usize, pi_address: u32) -> Result<(), DmaError> {
+        if len == 0 {
+            return Err(DmaError::Empty);
+        }
+
+        if ptr as usize % 8 != 0 {
+            return Err(DmaError::UnalignedRam);
+        }
+
+        if pi_address % 2 != 0 {
+            return Err(DmaError::UnalignedPi);
+        }
+
+        if len % 2 != 0 {
+            return Err(DmaError::OddLength);
+        }
+
+        return Ok(());
+    }
+
+    /// Write to a peripheral.
     ///
     /// This function should be used when writing to the cartridge.
     pub fn write(ram_address_in: &mut c_void, pi_address: u32, length: u32) {
@@ -1084,6 +2799,60 @@ pub mod DMA {
         unsafe { bindings::dma_read(ram_address_out, pi_address, length); }
     }
 
+    /// Read from a peripheral into a slice, taking the length from the slice.
+    ///
+    /// The slice must start on an 8-byte boundary and have an even length, and
+    /// pi_address must be even. The DMA bypasses the data cache, so invalidate
+    /// it over buf (see N64System::data_cache_hit_invalidate()) before reading
+    /// the data through a cached address.
+    pub fn read_into(buf: &mut [u8], pi_address: u32) -> Result<(), DmaError> {
+        check_dma(buf.as_ptr(), buf.len(), pi_address)?;
+
+        unsafe { bindings::dma_read(buf.as_mut_ptr().cast(), pi_address, buf.len() as u32); }
+
+        return Ok(());
+    }
+
+    /// Read from a peripheral into a slice and wait until the data can be used.
+    ///
+    /// Like read_into(), but also handles the data cache and waits for the
+    /// DMA controller to go idle, so buf can be read as soon as this returns.
+    /// buf must be in cached RDRAM (KSEG0, where Rust statics and the stack
+    /// live). Cache lines are 16 bytes, so data sharing a line with either end
+    /// of buf must not be written by interrupt handlers during the transfer.
+    pub fn read_blocking(buf: &mut [u8], pi_address: u32) -> Result<(), DmaError> {
+        check_dma(buf.as_ptr(), buf.len(), pi_address)?;
+
+        let length = buf.len() as u32;
+        let ram = unsafe { &mut *(buf.as_mut_ptr() as *mut c_void) };
+
+        // Flush dirty lines first so none get evicted over the incoming data.
+        N64System::data_cache_hit_writeback_invalidate(ram, length);
+        unsafe { bindings::dma_read(ram, pi_address, length); }
+
+        while get_busy().extract_inner() != 0 {}
+
+        N64System::data_cache_hit_invalidate(ram, length);
+
+        return Ok(());
+    }
+
+    /// Write a slice to a peripheral, taking the length from the slice.
+    ///
+    /// Alignment rules are as for read_into(). The data cache is written back
+    /// over buf first so the DMA sees its current contents.
+    pub fn write_from(buf: &[u8], pi_address: u32) -> Result<(), DmaError> {
+        check_dma(buf.as_ptr(), buf.len(), pi_address)?;
+
+        unsafe {
+            // Writeback only reads the cache lines; buf itself isn't modified.
+            bindings::data_cache_hit_writeback(Volatile::new(buf.as_ptr() as *mut c_void), buf.len() as c_ulong);
+            bindings::dma_write(buf.as_ptr() as *mut c_void, pi_address, buf.len() as u32);
+        }
+
+        return Ok(());
+    }
+
     /// Return whether the DMA controller is currently busy.
     ///
     /// Returns: nonzero if the DMA controller is busy or 0 otherwise
@@ -1133,13 +2902,13 @@ pub mod DMA {
 /// Files can be opened using both sets of API calls simultaneously as long
 /// as no more than four files are open at any one time.
 pub mod DragonFS {
-    use cstr_core::CStr;
     use cty::*;
 
     use crate::bindings;
 
     pub type DFSHandle = uint32_t;
 
+    #[derive(Debug)]
     pub enum DFSResult {
         Success = 0,
         BadInput = -1,
@@ -1149,6 +2918,21 @@ pub mod DragonFS {
         BadHandle = -5
     }
 
+    impl core::fmt::Display for DFSResult {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let description = match self {
+                DFSResult::Success => "success",
+                DFSResult::BadInput => "invalid input",
+                DFSResult::NoFile => "file or directory not found",
+                DFSResult::BadFS => "invalid filesystem",
+                DFSResult::NoMem => "out of memory",
+                DFSResult::BadHandle => "invalid file handle"
+            };
+
+            return f.write_str(description);
+        }
+    }
+
     #[macro_export] macro_rules! DFS_DEFAULT_LOCATION {() => (0xB0101000)}
     #[macro_export] macro_rules! MAX_OPEN_FILES {() => (4)}
     #[macro_export] macro_rules! MAX_FILENAME_LEN {() => (243)}
@@ -1158,7 +2942,26 @@ pub mod DragonFS {
     #[macro_export] macro_rules! FLAGS_EOF {() => (0x2)}
 
     /// Macro to extract the file type from a DragonFS file flag.
-    #[macro_export] macro_rules! FILETYPE {($x:expr) => (x & 3)}
+    #[macro_export] macro_rules! FILETYPE {($x:expr) => ($x & 3)}
+
+    /// Longest path, in bytes, accepted by the path functions.
+    pub const MAX_PATH_LEN: usize = 255;
+
+    // Copy a path into a null-terminated buffer that outlives the FFI call. A
+    // single trailing NUL is accepted for callers that already terminate their
+    // paths; interior NULs and overlong paths are rejected.
+    fn c_path(path: &str) -> Result<[u8; MAX_PATH_LEN + 1], DFSResult> {
+        let path = path.strip_suffix('\0').unwrap_or(path);
+
+        if path.len() > MAX_PATH_LEN || path.as_bytes().contains(&0) {
+            return Err(DFSResult::BadInput);
+        }
+
+        let mut buffer = [0; MAX_PATH_LEN + 1];
+        buffer[..path.len()].copy_from_slice(path.as_bytes());
+
+        return Ok(buffer);
+    }
 
     /// Initialize the filesystem.
     ///
@@ -1184,12 +2987,16 @@ pub mod DragonFS {
     ///
     /// Supports absolute and relative
     ///
-    /// Note: path must be null-terminated.
+    /// Note: path must not contain NUL bytes or be longer than MAX_PATH_LEN,
+    /// otherwise BadInput is returned.
     pub fn chdir(path: &str) -> DFSResult {
-        unsafe {
-            let cstr: *const i8 = CStr::from_ptr(path.as_ptr().cast()).as_ptr();
+        let cstr = match c_path(path) {
+            Ok(cstr) => cstr,
+            Err(error) => return error
+        };
 
-            return match bindings::dfs_chdir(cstr) {
+        unsafe {
+            return match bindings::dfs_chdir(cstr.as_ptr().cast()) {
                 0 => DFSResult::Success,
                 -1 => DFSResult::BadInput,
                 -2 => DFSResult::NoFile,
@@ -1207,12 +3014,13 @@ pub mod DragonFS {
     /// DFSResult. If a file or directory is found, returns the flags of the entry
     /// and copies the name into buffer.
     ///
-    /// Note: path must be null-terminated.
+    /// Note: path must not contain NUL bytes or be longer than MAX_PATH_LEN,
+    /// otherwise BadInput is returned.
     pub fn dir_find_first(path: &str, buffer_out: &mut [c_char]) -> Result<i32, DFSResult> {
-        unsafe {
-            let cstr: *const i8 = CStr::from_ptr(path.as_ptr().cast()).as_ptr();
+        let cstr = c_path(path)?;
 
-            return match bindings::dfs_dir_findfirst(cstr, buffer_out.as_mut_ptr()) {
+        unsafe {
+            return match bindings::dfs_dir_findfirst(cstr.as_ptr().cast(), buffer_out.as_mut_ptr()) {
                 x @ 0..=3 => Ok(x),
                 -1 => Err(DFSResult::BadInput),
                 -2 => Err(DFSResult::NoFile),
@@ -1241,17 +3049,122 @@ pub mod DragonFS {
         }
     }
 
+    /// The type of a directory entry, decoded from its flags with FILETYPE.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum EntryKind {
+        File, // FLAGS_FILE
+        Dir   // FLAGS_DIR
+    }
+
+    /// An entry yielded by DirIter.
+    #[derive(Clone, Copy)]
+    pub struct DirEntry {
+        name: [u8; crate::MAX_FILENAME_LEN!() + 1],
+        kind: EntryKind
+    }
+
+    impl DirEntry {
+        /// The entry name, cut at the terminating NUL (or at the first invalid UTF-8 byte).
+        pub fn name(&self) -> &str {
+            let len = self.name.iter().position(|&c| c == 0).unwrap_or(self.name.len());
+
+            return match core::str::from_utf8(&self.name[..len]) {
+                Ok(name) => name,
+                Err(err) => unsafe { core::str::from_utf8_unchecked(&self.name[..err.valid_up_to()]) }
+            };
+        }
+
+        pub fn kind(&self) -> EntryKind {
+            return self.kind;
+        }
+    }
+
+    impl core::fmt::Debug for DirEntry {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            return f.debug_struct("DirEntry").field("name", &self.name()).field("kind", &self.kind).finish();
+        }
+    }
+
+    /// Iterator over a directory listing, created by read_dir().
+    ///
+    /// DragonFS keeps a single listing position, so starting another listing
+    /// (or calling dir_find_first()) while iterating restarts from that listing.
+    pub struct DirIter {
+        buffer: [c_char; crate::MAX_FILENAME_LEN!() + 1],
+        first: Option<i32>,
+        done: bool
+    }
+
+    impl DirIter {
+        fn entry(&self, flags: i32) -> DirEntry {
+            let mut name = [0; crate::MAX_FILENAME_LEN!() + 1];
+            for (out, &c) in name.iter_mut().zip(self.buffer.iter()) {
+                *out = c as u8;
+            }
+
+            let kind = match crate::FILETYPE!(flags) {
+                crate::FLAGS_DIR!() => EntryKind::Dir,
+                _ => EntryKind::File
+            };
+
+            return DirEntry { name, kind };
+        }
+    }
+
+    impl Iterator for DirIter {
+        type Item = DirEntry;
+
+        fn next(&mut self) -> Option<DirEntry> {
+            if self.done {
+                return None;
+            }
+
+            let flags = match self.first.take() {
+                Some(flags) => flags,
+                None => unsafe {
+                    match bindings::dfs_dir_findnext(self.buffer.as_mut_ptr()) {
+                        x @ 0..=3 => x,
+                        _ => {
+                            // NoFile marks the end of the listing; stop on errors too.
+                            self.done = true;
+                            return None;
+                        }
+                    }
+                }
+            };
+
+            return Some(self.entry(flags));
+        }
+    }
+
+    /// List the entries of a directory.
+    ///
+    /// An empty directory yields an empty iterator; other failures to start the
+    /// listing are returned as errors.
+    pub fn read_dir(path: &str) -> Result<DirIter, DFSResult> {
+        let mut iter = DirIter { buffer: [0; crate::MAX_FILENAME_LEN!() + 1], first: None, done: false };
+
+        match dir_find_first(path, &mut iter.buffer) {
+            Ok(flags) => iter.first = Some(flags),
+            Err(DFSResult::NoFile) => iter.done = true,
+            Err(error) => return Err(error)
+        }
+
+        return Ok(iter);
+    }
+
     /// Open a file given a path.
     ///
     /// Check if we have any free file handles, and if we do, try to open
     /// the file specified. Supports absolute and relative paths
     ///
-    /// Note: path must be null-terminated.
+    /// Note: path must not contain NUL bytes or be longer than MAX_PATH_LEN,
+    /// otherwise BadInput is returned.
     pub fn open(path: &str) -> Result<u32, DFSResult> {
-        unsafe {
-            let cstr: *const i8 = CStr::from_ptr(path.as_ptr().cast()).as_ptr();
+        let cstr = c_path(path)?;
 
-            return match bindings::dfs_open(cstr) {
+        unsafe {
+            return match bindings::dfs_open(cstr.as_ptr().cast()) {
                 -1 => Err(DFSResult::BadInput),
                 -2 => Err(DFSResult::NoFile),
                 -3 => Err(DFSResult::BadFS),
@@ -1278,8 +3191,54 @@ pub mod DragonFS {
         }
     }
 
+    /// Read from a file into a byte slice.
+    ///
+    /// Reads up to buf.len() bytes, clamped to what is left in the file, and
+    /// returns the number of bytes read. Returns Ok(0) at the end of the file.
+    pub fn read_into(handle: DFSHandle, buf: &mut [u8]) -> Result<usize, DFSResult> {
+        let remaining = (size(handle)? - tell(handle)?).max(0) as usize;
+        let count = buf.len().min(remaining).min(c_int::MAX as usize);
+
+        if count == 0 {
+            return Ok(0);
+        }
+
+        unsafe {
+            return match bindings::dfs_read(buf.as_mut_ptr().cast(), 1, count as c_int, handle) {
+                x @ 0..=c_int::MAX => Ok(x as usize),
+                -1 => Err(DFSResult::BadInput),
+                -2 => Err(DFSResult::NoFile),
+                -3 => Err(DFSResult::BadFS),
+                -4 => Err(DFSResult::NoMem),
+                -5 => Err(DFSResult::BadHandle),
+                bad => panic!("Invalid result from DragonFS::read_into(): {}", bad)
+            }
+        }
+    }
+
+    /// Position to seek to, relative to the start, the current offset or the end of a file.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum SeekFrom {
+        Start(i32),   // SEEK_SET
+        Current(i32), // SEEK_CUR
+        End(i32)      // SEEK_END
+    }
+
+    impl SeekFrom {
+        // The offset and newlib origin constant passed to dfs_seek().
+        fn to_raw(self) -> (c_int, c_int) {
+            return match self {
+                SeekFrom::Start(offset) => (offset, 0),
+                SeekFrom::Current(offset) => (offset, 1),
+                SeekFrom::End(offset) => (offset, 2)
+            };
+        }
+    }
+
     /// Seek to an offset in the file.
-    pub fn seek(handle: DFSHandle, offset: i32, origin: i32) -> DFSResult {
+    pub fn seek(handle: DFSHandle, pos: SeekFrom) -> DFSResult {
+        let (offset, origin) = pos.to_raw();
+
         unsafe {
             return match bindings::dfs_seek(handle, offset, origin) {
                 0 => DFSResult::Success,
@@ -1353,6 +3312,96 @@ pub mod DragonFS {
             };
         }
     }
+
+    /// An open file, closed when dropped.
+    ///
+    /// Only MAX_OPEN_FILES handles can be open at once, so keep files open only
+    /// as long as they are needed.
+    pub struct File {
+        handle: DFSHandle
+    }
+
+    impl File {
+        /// The underlying handle, for use with the free functions in this module.
+        pub fn handle(&self) -> DFSHandle {
+            return self.handle;
+        }
+
+        /// Read into a byte slice, returning the number of bytes read. See read_into().
+        pub fn read(&mut self, buf: &mut [u8]) -> Result<usize, DFSResult> {
+            return read_into(self.handle, buf);
+        }
+
+        /// Seek to an offset in the file.
+        pub fn seek(&mut self, pos: SeekFrom) -> Result<(), DFSResult> {
+            return match seek(self.handle, pos) {
+                DFSResult::Success => Ok(()),
+                error => Err(error)
+            };
+        }
+
+        /// Return the current offset into the file.
+        pub fn tell(&self) -> Result<i32, DFSResult> {
+            return tell(self.handle);
+        }
+
+        /// Return the size of the file.
+        pub fn size(&self) -> Result<i32, DFSResult> {
+            return size(self.handle);
+        }
+
+        /// Return whether the end of file has been reached.
+        pub fn eof(&self) -> Result<bool, DFSResult> {
+            return eof(self.handle);
+        }
+    }
+
+    impl Drop for File {
+        fn drop(&mut self) {
+            close(self.handle);
+        }
+    }
+
+    /// Open a file given a path, returning a File that closes it when dropped.
+    ///
+    /// See open() for the accepted paths.
+    pub fn open_file(path: &str) -> Result<File, DFSResult> {
+        return Ok(File { handle: open(path)? });
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn c_path_accepts_longest_path() {
+            let path = "a".repeat(MAX_PATH_LEN);
+            let buffer = c_path(&path).unwrap();
+
+            assert_eq!(&buffer[..MAX_PATH_LEN], path.as_bytes());
+            assert_eq!(buffer[MAX_PATH_LEN], 0);
+        }
+
+        #[test]
+        fn c_path_rejects_overlong_path() {
+            let path = "a".repeat(MAX_PATH_LEN + 1);
+
+            assert!(matches!(c_path(&path), Err(DFSResult::BadInput)));
+        }
+
+        #[test]
+        fn c_path_strips_one_trailing_nul() {
+            let path = "a".repeat(MAX_PATH_LEN) + "\0";
+
+            assert!(c_path(&path).is_ok());
+            assert!(matches!(c_path("dir/file\0\0"), Err(DFSResult::BadInput)));
+        }
+
+        #[test]
+        fn c_path_rejects_interior_nul() {
+            assert!(matches!(c_path("dir\0/file"), Err(DFSResult::BadInput)));
+        }
+    }
 }
 
 /// Software routines for manipulating graphics in a display context.
@@ -1378,12 +3427,15 @@ pub mod DragonFS {
 /// make_color() and convert_color() are also compatible with both hardware and software
 /// graphics routines.
 pub mod GraphicsEngine {
-    use cstr_core::CStr;
     use cty::*;
 
-    use crate::{Display::DisplayContext, bindings};
+    use crate::{Display::{self, BitDepth, DisplayContext}, Rect, bindings};
+
+    // Bytes of text handed to graphics_draw_text() per call by draw_text().
+    const TEXT_CHUNK: usize = 127;
 
     #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
     pub struct RGBColor {
         pub r: uint8_t,
         pub g: uint8_t,
@@ -1391,6 +3443,34 @@ pub mod GraphicsEngine {
         pub a: uint8_t
     }
 
+    impl RGBColor {
+        pub const BLACK: RGBColor = RGBColor::new(0, 0, 0, 255);
+        pub const WHITE: RGBColor = RGBColor::new(255, 255, 255, 255);
+        pub const RED: RGBColor = RGBColor::new(255, 0, 0, 255);
+        pub const GREEN: RGBColor = RGBColor::new(0, 255, 0, 255);
+        pub const BLUE: RGBColor = RGBColor::new(0, 0, 255, 255);
+        pub const TRANSPARENT: RGBColor = RGBColor::new(0, 0, 0, 0);
+
+        pub const fn new(r: u8, g: u8, b: u8, a: u8) -> RGBColor {
+            return RGBColor { r, g, b, a };
+        }
+
+        /// An opaque color from a packed 0xRRGGBB value.
+        pub const fn from_rgb(rgb: u32) -> RGBColor {
+            return RGBColor::new((rgb >> 16) as u8, (rgb >> 8) as u8, rgb as u8, 255);
+        }
+
+        /// A color from a packed 0xRRGGBBAA value.
+        pub const fn from_rgba(rgba: u32) -> RGBColor {
+            return RGBColor::new((rgba >> 24) as u8, (rgba >> 16) as u8, (rgba >> 8) as u8, rgba as u8);
+        }
+
+        /// Convert to the framebuffer format of the current bit depth. See convert_color().
+        pub fn to_n64(&self) -> N64Color {
+            return convert_color(*self);
+        }
+    }
+
     pub type N64Color = uint32_t;
 
     #[repr(C)]
@@ -1404,15 +3484,69 @@ pub mod GraphicsEngine {
         pub data: [uint32_t; 0]
     }
 
-    /// Return a 32-bit representation of an RGBA color.
-    pub fn make_color(r: i32, g: i32, b: i32, a: i32) -> N64Color {
-        unsafe { return bindings::graphics_make_color(r, g, b, a); }
-    }
+    impl Sprite {
+        /// Bytes per pixel of the image data (libdragon stores the bit depth in bytes).
+        pub fn bytes_per_pixel(&self) -> u32 {
+            return self.bitdepth as u32;
+        }
 
-    /// Convert a color structure to a 32-bit representation of an RGBA color.
-    pub fn convert_color(color: RGBColor) -> N64Color {
-        unsafe { return bindings::graphics_convert_color(color); }
-    }
+        /// Number of tiles in a spritemap, or 1 for a plain sprite.
+        pub fn slice_count(&self) -> u32 {
+            return self.hslices.max(1) as u32 * self.vslices.max(1) as u32;
+        }
+
+        /// Return whether the sprite is split into more than one tile.
+        pub fn is_spritemap(&self) -> bool {
+            return self.slice_count() > 1;
+        }
+
+        /// Width in pixels of a single tile.
+        pub fn tile_width(&self) -> u32 {
+            return self.width as u32 / self.hslices.max(1) as u32;
+        }
+
+        /// Height in pixels of a single tile.
+        pub fn tile_height(&self) -> u32 {
+            return self.height as u32 / self.vslices.max(1) as u32;
+        }
+    }
+
+    /// Return a 32-bit representation of an RGBA color.
+    pub fn make_color(r: i32, g: i32, b: i32, a: i32) -> N64Color {
+        unsafe { return bindings::graphics_make_color(r, g, b, a); }
+    }
+
+    /// Convert a color structure to a 32-bit representation of an RGBA color.
+    pub fn convert_color(color: RGBColor) -> N64Color {
+        unsafe { return bindings::graphics_convert_color(color); }
+    }
+
+    /// Unpack a color made by convert_color() or make_color() back into its components.
+    ///
+    /// The packing depends on the bit depth the display was initialized with,
+    /// which has to be given here. At 16bpp the 5-bit channels are widened back
+    /// to 8 bits and alpha is either 0 or 255, so converting the result again
+    /// gives back the same N64Color.
+    pub fn unconvert_color(c: N64Color, bitdepth: BitDepth) -> RGBColor {
+        return match bitdepth {
+            BitDepth::DEPTH_16_BPP => {
+                let widen = |v: u32| -> u8 { ((v << 3) | (v >> 2)) as u8 };
+
+                RGBColor {
+                    r: widen((c >> 11) & 0x1F),
+                    g: widen((c >> 6) & 0x1F),
+                    b: widen((c >> 1) & 0x1F),
+                    a: if c & 1 != 0 { 255 } else { 0 }
+                }
+            },
+            BitDepth::DEPTH_32_BPP => RGBColor {
+                r: (c >> 24) as u8,
+                g: (c >> 16) as u8,
+                b: (c >> 8) as u8,
+                a: c as u8
+            }
+        };
+    }
 
     /// Draw a pixel to a given display context.
     pub fn draw_pixel(disp: DisplayContext, x: i32, y: i32, c: N64Color) {
@@ -1444,6 +3578,65 @@ pub mod GraphicsEngine {
         unsafe { bindings::graphics_draw_box_trans(disp, x, y, width, height, color); }
     }
 
+    /// Draw a filled rectangle described by a Rect to a display context.
+    pub fn draw_rect(disp: DisplayContext, rect: Rect, color: N64Color) {
+        draw_box(disp, rect.x, rect.y, rect.w, rect.h, color);
+    }
+
+    /// Draw a batch of filled rectangles in a single color.
+    ///
+    /// Software counterpart of RDP::fill_rects(). Empty rectangles are skipped.
+    pub fn fill_rects(disp: DisplayContext, color: N64Color, rects: &[Rect]) {
+        for rect in rects {
+            if !rect.is_empty() {
+                draw_rect(disp, *rect, color);
+            }
+        }
+    }
+
+    /// The screen area of the resolution passed to Display::init(), or an
+    /// empty rectangle if the display isn't initialized.
+    pub fn screen_rect() -> Rect {
+        return Rect::new(0, 0, Display::width() as i32, Display::height() as i32);
+    }
+
+    /// Like draw_pixel(), but skip pixels outside the screen.
+    ///
+    /// Returns whether the pixel was drawn.
+    pub fn draw_pixel_checked(disp: DisplayContext, x: i32, y: i32, c: N64Color) -> bool {
+        if !screen_rect().contains_point(x, y) {
+            return false;
+        }
+
+        draw_pixel(disp, x, y, c);
+        return true;
+    }
+
+    /// Like draw_pixel_trans(), but skip pixels outside the screen.
+    ///
+    /// Returns whether the pixel was drawn.
+    pub fn draw_pixel_trans_checked(disp: DisplayContext, x: i32, y: i32, c: N64Color) -> bool {
+        if !screen_rect().contains_point(x, y) {
+            return false;
+        }
+
+        draw_pixel_trans(disp, x, y, c);
+        return true;
+    }
+
+    /// Like draw_box(), but clip the box to the screen.
+    ///
+    /// Returns whether any of the box was on screen and drawn.
+    pub fn draw_box_checked(disp: DisplayContext, x: i32, y: i32, width: i32, height: i32, color: N64Color) -> bool {
+        return match screen_rect().intersection(&Rect::new(x, y, width, height)) {
+            Some(visible) => {
+                draw_rect(disp, visible, color);
+                true
+            },
+            None => false
+        };
+    }
+
     /// Fill the entire screen with a particular color.
     pub fn fill_screen(disp: DisplayContext, c: N64Color) {
         unsafe { bindings::graphics_fill_screen(disp, c); }
@@ -1475,12 +3668,30 @@ pub mod GraphicsEngine {
     /// Otherwise, the font is drawn on a fully colored background. The foreground and
     /// background can be set using set_color().
     ///
-    /// Note: msg must be null-terminated.
+    /// Note: msg is copied into null-terminated chunks before being handed to
+    /// libdragon, so it needn't be terminated. Drawing stops at the first NUL byte.
     pub fn draw_text(disp: DisplayContext, x: i32, y: i32, msg: &str) {
-        unsafe {
-            let cstr: *const i8 = CStr::from_ptr(msg.as_ptr().cast()).as_ptr();
+        let msg = match msg.find('\0') {
+            Some(end) => &msg[..end],
+            None => msg
+        };
+
+        // Draw line by line, in chunks that fit the buffer, advancing the
+        // position the same way libdragon does within a single call.
+        let mut line_y = y;
+        for line in msg.split(|c| c == '\n' || c == '\r') {
+            let mut column = 0;
+
+            for chunk in line.as_bytes().chunks(TEXT_CHUNK) {
+                let mut cstr = [0u8; TEXT_CHUNK + 1];
+                cstr[..chunk.len()].copy_from_slice(chunk);
+
+                unsafe { bindings::graphics_draw_text(disp, x + column * 8, line_y, cstr.as_ptr().cast()); }
 
-            bindings::graphics_draw_text(disp, x, y, cstr);
+                column += chunk.iter().map(|&c| if c == b'\t' { 5 } else { 1 }).sum::<i32>();
+            }
+
+            line_y += 8;
         }
     }
 
@@ -1556,74 +3767,119 @@ pub mod Interrupt {
 
     pub type InterruptFlag = bool;
 
+    /// A hardware interrupt that handlers can be registered for.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum InterruptSource {
+        AI, // Audio interface
+        VI, // Video interface
+        PI, // Peripheral interface
+        DP, // RDP
+        TI, // Timer
+        SI, // Serial interface (controllers)
+        SP  // RSP
+    }
+
+    /// Register a callback for an interrupt source.
+    ///
+    /// libdragon keeps the function pointer itself, and several callbacks may
+    /// be registered for the same source.
+    pub fn register(source: InterruptSource, callback: extern "C" fn()) {
+        unsafe {
+            match source {
+                InterruptSource::AI => bindings::register_AI_handler(Some(callback)),
+                InterruptSource::VI => bindings::register_VI_handler(Some(callback)),
+                InterruptSource::PI => bindings::register_PI_handler(Some(callback)),
+                InterruptSource::DP => bindings::register_DP_handler(Some(callback)),
+                InterruptSource::TI => bindings::register_TI_handler(Some(callback)),
+                InterruptSource::SI => bindings::register_SI_handler(Some(callback)),
+                InterruptSource::SP => bindings::register_SP_handler(Some(callback))
+            }
+        }
+    }
+
+    /// Unregister a callback previously passed to register() for the same source.
+    pub fn unregister(source: InterruptSource, callback: extern "C" fn()) {
+        unsafe {
+            match source {
+                InterruptSource::AI => bindings::unregister_AI_handler(Some(callback)),
+                InterruptSource::VI => bindings::unregister_VI_handler(Some(callback)),
+                InterruptSource::PI => bindings::unregister_PI_handler(Some(callback)),
+                InterruptSource::DP => bindings::unregister_DP_handler(Some(callback)),
+                InterruptSource::TI => bindings::unregister_TI_handler(Some(callback)),
+                InterruptSource::SI => bindings::unregister_SI_handler(Some(callback)),
+                InterruptSource::SP => bindings::unregister_SP_handler(Some(callback))
+            }
+        }
+    }
+
     /// Register an AI callback.
     pub fn register_AI_handler(callback: extern "C" fn()) {
-        unsafe { bindings::register_AI_handler(Some(callback)); }
+        register(InterruptSource::AI, callback);
     }
 
     /// Register a VI callback.
     pub fn register_VI_handler(callback: extern "C" fn()) {
-        unsafe { bindings::register_VI_handler(Some(callback)); }
+        register(InterruptSource::VI, callback);
     }
 
     /// Register a PI callback.
     pub fn register_PI_handler(callback: extern "C" fn()) {
-        unsafe { bindings::register_PI_handler(Some(callback)); }
+        register(InterruptSource::PI, callback);
     }
 
     /// Register a DP callback.
     pub fn register_DP_handler(callback: extern "C" fn()) {
-        unsafe { bindings::register_DP_handler(Some(callback)); }
+        register(InterruptSource::DP, callback);
     }
 
     /// Register a TI callback.
     pub fn register_TI_handler(callback: extern "C" fn()) {
-        unsafe { bindings::register_TI_handler(Some(callback)); }
+        register(InterruptSource::TI, callback);
     }
 
     /// Register an SI callback.
     pub fn register_SI_handler(callback: extern "C" fn()) {
-        unsafe { bindings::register_SI_handler(Some(callback)); }
+        register(InterruptSource::SI, callback);
     }
 
     /// Register an SP callback.
     pub fn register_SP_handler(callback: extern "C" fn()) {
-        unsafe { bindings::register_SP_handler(Some(callback)); }
+        register(InterruptSource::SP, callback);
     }
 
     /// Unregister an AI callback.
     pub fn unregister_AI_handler(callback: extern "C" fn()) {
-        unsafe { bindings::unregister_AI_handler(Some(callback)); }
+        unregister(InterruptSource::AI, callback);
     }
 
     /// Unregister a VI callback.
     pub fn unregister_VI_handler(callback: extern "C" fn()) {
-        unsafe { bindings::unregister_VI_handler(Some(callback)); }
+        unregister(InterruptSource::VI, callback);
     }
 
     /// Unregister a PI callback.
     pub fn unregister_PI_handler(callback: extern "C" fn()) {
-        unsafe { bindings::unregister_PI_handler(Some(callback)); }
+        unregister(InterruptSource::PI, callback);
     }
 
     /// Unregister a DP callback.
     pub fn unregister_DP_handler(callback: extern "C" fn()) {
-        unsafe { bindings::unregister_DP_handler(Some(callback)); }
+        unregister(InterruptSource::DP, callback);
     }
 
     /// Unregister a TI callback.
     pub fn unregister_TI_handler(callback: extern "C" fn()) {
-        unsafe { bindings::unregister_TI_handler(Some(callback)); }
+        unregister(InterruptSource::TI, callback);
     }
 
     /// Unregister an SI callback.
     pub fn unregister_SI_handler(callback: extern "C" fn()) {
-        unsafe { bindings::unregister_SI_handler(Some(callback)); }
+        unregister(InterruptSource::SI, callback);
     }
 
     /// Unregister an SP callback.
     pub fn unregister_SP_handler(callback: extern "C" fn()) {
-        unsafe { bindings::unregister_SP_handler(Some(callback)); }
+        unregister(InterruptSource::SP, callback);
     }
 
     /// Enable or disable AI interrupt.
@@ -1682,6 +3938,50 @@ pub mod Interrupt {
     pub fn get_interrupts_state() -> InterruptState {
         unsafe { return bindings::get_interrupts_state(); }
     }
+
+    /// Keeps interrupts disabled until dropped, created by critical().
+    ///
+    /// Guards nest: libdragon counts disable calls, so interrupts only come back
+    /// on when the outermost guard is dropped, and stay off if they were off
+    /// before it was created.
+    ///
+    /// ```no_run
+    /// use libdragon_bindings::Interrupt::{self, InterruptState};
+    ///
+    /// let outer = Interrupt::critical();
+    ///
+    /// Interrupt::with_disabled(|| {
+    ///     let _inner = Interrupt::critical();
+    /// });
+    ///
+    /// // The inner guards are gone, but outer still holds interrupts off.
+    /// assert!(matches!(Interrupt::get_interrupts_state(), InterruptState::INTERRUPTS_DISABLED));
+    ///
+    /// drop(outer);
+    /// ```
+    pub struct CriticalSection {
+        _private: ()
+    }
+
+    impl Drop for CriticalSection {
+        fn drop(&mut self) {
+            enable_interrupts();
+        }
+    }
+
+    /// Disable interrupts until the returned guard is dropped.
+    pub fn critical() -> CriticalSection {
+        disable_interrupts();
+
+        return CriticalSection { _private: () };
+    }
+
+    /// Run a closure with interrupts disabled, restoring them afterwards. Nests like critical().
+    pub fn with_disabled<R>(f: impl FnOnce() -> R) -> R {
+        let _guard = critical();
+
+        return f();
+    }
 }
 
 /// N64 bootup and cache interfaces.
@@ -1697,9 +3997,14 @@ pub mod Interrupt {
 /// provided for both instruction cache and data cache.
 #[allow(dead_code)]
 pub mod N64System {
+    #[cfg(feature = "allocator")]
+    use core::alloc::{GlobalAlloc, Layout};
+
     use cty::c_void;
     use volatile::Volatile;
 
+    #[cfg(feature = "allocator")]
+    use crate::Interrupt;
     use crate::bindings;
 
     #[repr(C)]
@@ -1709,6 +4014,13 @@ pub mod N64System {
         TV_MPAL = 2
     }
 
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ResetType {
+        ColdBoot = 0, // Console was powered on
+        NmiReset = 1  // Console was reset via the reset button (NMI)
+    }
+
     /// Number of updates to the count register per second.
     ///
     /// Every second, this many counts will have passed in the count register
@@ -1890,6 +4202,85 @@ pub mod N64System {
     pub fn get_tv_type() -> TVType {
         unsafe { return bindings::get_tv_type(); }
     }
+
+    /// Was the system cold booted or reset via NMI.
+    ///
+    /// libdragon does not expose the reset type, so this reads the word the boot
+    /// code leaves at 0x8000030C (osResetType in libultra terms), next to the TV
+    /// type read by get_tv_type(). The PIF passes the reset type to IPL3 in $s5,
+    /// which stores 0 on a cold boot and 1 on a reset button (NMI) press. Any
+    /// other value is treated as a cold boot.
+    pub fn reset_type() -> ResetType {
+        let reset_type = get_uncached_unsigned_long_address(0x8000_030C);
+
+        unsafe {
+            return match core::ptr::read_volatile(reset_type) {
+                1 => ResetType::NmiReset,
+                _ => ResetType::ColdBoot
+            };
+        }
+    }
+
+    /// Bytes at the top of RDRAM left to the stack by heap_range().
+    pub const STACK_RESERVE: usize = 64 * 1024;
+
+    /// The free memory between the end of the program image and the stack.
+    ///
+    /// Returns (start, end) as cached KSEG0 addresses, with start aligned to 16
+    /// bytes. The stack starts at the top of RDRAM (which depends on whether an
+    /// expansion pak is present) and grows down; STACK_RESERVE bytes are left
+    /// to it. Note that libdragon's malloc() hands out memory from the same range.
+    /// HeapAllocator doesn't use this range directly; it allocates through malloc.
+    pub fn heap_range() -> (usize, usize) {
+        let image_end = unsafe { &bindings::end as *const u8 as usize };
+        let start = (image_end + 15) & !15;
+        let end = 0x8000_0000 + get_memory_size() as usize - STACK_RESERVE;
+
+        return (start, end);
+    }
+
+    /// The global allocator, backed by newlib's memalign() and free().
+    ///
+    /// Going through newlib means Rust allocations share the heap with
+    /// libdragon's own (display buffers, the console, timers, DragonFS) and with
+    /// any C code calling malloc(), instead of competing with them for the
+    /// memory in heap_range(). Calls run with interrupts disabled, since newlib's
+    /// malloc has no locking here; that makes Rust allocations safe from
+    /// interrupt handlers and callbacks, but C code must not call malloc() from
+    /// an interrupt while the main program allocates.
+    #[cfg(feature = "allocator")]
+    pub struct HeapAllocator;
+
+    #[cfg(feature = "allocator")]
+    unsafe impl GlobalAlloc for HeapAllocator {
+        unsafe fn alloc(&self, layout: Layout) -> *mut u8 {
+            Interrupt::disable_interrupts();
+            let result = bindings::memalign(layout.align(), layout.size());
+            Interrupt::enable_interrupts();
+
+            return result.cast();
+        }
+
+        unsafe fn dealloc(&self, ptr: *mut u8, _layout: Layout) {
+            Interrupt::disable_interrupts();
+            bindings::free(ptr.cast());
+            Interrupt::enable_interrupts();
+        }
+    }
+
+    // Unit tests run on the host under std's allocator.
+    #[cfg(all(feature = "allocator", not(test)))]
+    #[global_allocator]
+    static ALLOCATOR: HeapAllocator = HeapAllocator;
+
+    /// Set up the global allocator.
+    ///
+    /// Does nothing: HeapAllocator needs no setup, as newlib sets up its heap at
+    /// startup and alloc works from the start. It is not given heap_range(),
+    /// since newlib already manages that memory. Kept so startup code written
+    /// for an allocator that needs initializing keeps working.
+    #[cfg(feature = "allocator")]
+    pub fn init_allocator() {}
 }
 
 /// N64 COP0 Interface.
@@ -1909,6 +4300,108 @@ pub mod COP0 {
     #[macro_export] macro_rules! C0_INTERRUPT_6 {() => (0x0000_4000)}
     #[macro_export] macro_rules! C0_INTERRUPT_TIMER {() => (0x0000_8000)}
 
+    /// Typed view of the COP0 Status register.
+    ///
+    /// Wraps the raw value returned by STATUS() so the C0_STATUS_* bits can be
+    /// inspected and changed by name. Setters return a new value, so a change
+    /// reads as Status::read().with_interrupts_enabled(false).write().
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Status(pub u32);
+
+    impl Status {
+        /// Read the COP0 Status register.
+        pub fn read() -> Status {
+            return Status(STATUS());
+        }
+
+        /// Write this value to the COP0 Status register.
+        pub fn write(self) {
+            WRITE_STATUS(self.0);
+        }
+
+        /// Return the raw register value.
+        pub fn bits(self) -> u32 {
+            return self.0;
+        }
+
+        /// Global interrupt enable (IE).
+        pub fn interrupts_enabled(self) -> bool {
+            return self.0 & crate::C0_STATUS_IE!() != 0;
+        }
+
+        /// Exception level (EXL), set by the CPU when an exception is taken.
+        pub fn exception_level(self) -> bool {
+            return self.0 & crate::C0_STATUS_EXL!() != 0;
+        }
+
+        /// Error level (ERL), set by the CPU on reset, NMI or cache errors.
+        pub fn error_level(self) -> bool {
+            return self.0 & crate::C0_STATUS_ERL!() != 0;
+        }
+
+        /// Return a copy with the IE bit set or cleared.
+        pub fn with_interrupts_enabled(self, enabled: bool) -> Status {
+            return self.with_bits(crate::C0_STATUS_IE!(), enabled);
+        }
+
+        /// Return a copy with the EXL bit set or cleared.
+        pub fn with_exception_level(self, set: bool) -> Status {
+            return self.with_bits(crate::C0_STATUS_EXL!(), set);
+        }
+
+        /// Return a copy with the ERL bit set or cleared.
+        pub fn with_error_level(self, set: bool) -> Status {
+            return self.with_bits(crate::C0_STATUS_ERL!(), set);
+        }
+
+        fn with_bits(self, mask: u32, set: bool) -> Status {
+            if set {
+                return Status(self.0 | mask);
+            }
+
+            return Status(self.0 & !mask);
+        }
+    }
+
+    /// Typed view of the COP0 Cause register.
+    ///
+    /// Wraps the raw value returned by READ_CR() so the C0_CAUSE_* fields can
+    /// be read by name.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Cause(pub u32);
+
+    impl Cause {
+        /// Read the COP0 Cause register.
+        pub fn read() -> Cause {
+            return Cause(READ_CR());
+        }
+
+        /// Return the raw register value.
+        pub fn bits(self) -> u32 {
+            return self.0;
+        }
+
+        /// The exception was taken in a branch delay slot (BD), so EPC points at the branch.
+        pub fn branch_delay(self) -> bool {
+            return self.0 & crate::C0_CAUSE_BD!() != 0;
+        }
+
+        /// Coprocessor number for a coprocessor unusable exception (CE).
+        pub fn coprocessor(self) -> u32 {
+            return (self.0 & crate::C0_CAUSE_CE!()) >> 28;
+        }
+
+        /// Raw exception code (ExcCode), as in ExceptionCode.
+        pub fn exception_code(self) -> u32 {
+            return (self.0 & crate::C0_CAUSE_EXC_CODE!()) >> 2;
+        }
+
+        /// Return whether any of the given C0_INTERRUPT_* bits are pending.
+        pub fn interrupt_pending(self, mask: u32) -> bool {
+            return self.0 & mask != 0;
+        }
+    }
+
     /// Read the COP0 Count register
     pub fn COUNT() -> u32 {
         let x: u32;
@@ -2014,6 +4507,115 @@ pub mod COP1 {
     #[macro_export] macro_rules! C1_CAUSE_INVALID_OP {() => (0x0001_0000)}
     #[macro_export] macro_rules! C1_CAUSE_NOT_IMPLEMENTED {() => (0x0002_0000)}
 
+    const C1_FLAG_MASK: u32 = 0x0000_007C;
+    const C1_ENABLE_MASK: u32 = 0x0000_0F80;
+    const C1_CAUSE_MASK: u32 = 0x0003_F000;
+    const C1_ROUNDING_MODE_MASK: u32 = 0x0000_0003;
+
+    /// FPU rounding modes, stored in the low two bits of FCR31.
+    #[repr(u32)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum RoundingMode {
+        Nearest = 0,       // Round to nearest representable value
+        Zero = 1,          // Round toward zero
+        PlusInfinity = 2,  // Round toward +infinity
+        MinusInfinity = 3  // Round toward -infinity
+    }
+
+    /// Typed view of the COP1 FCR31 (Control/Status) register.
+    ///
+    /// FCR31 holds three groups of exception bits. Flag bits are sticky and record
+    /// every exception since they were last cleared, enable bits select which
+    /// exceptions trap, and cause bits describe the last operation. A floating-point
+    /// exception handler must clear the cause bits (see clear_cause_bits()) or the
+    /// exception will fire again as soon as the handler returns.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Fcr31(pub u32);
+
+    impl Fcr31 {
+        /// Read the COP1 FCR31 register.
+        pub fn read() -> Fcr31 {
+            return Fcr31(FCR31());
+        }
+
+        /// Write this value to the COP1 FCR31 register.
+        pub fn write(self) {
+            WRITE_FCR31(self.0);
+        }
+
+        /// Return the raw register value.
+        pub fn bits(self) -> u32 {
+            return self.0;
+        }
+
+        pub fn flag_inexact(self) -> bool { return self.0 & crate::C1_FLAG_INEXACT_OP!() != 0; }
+        pub fn flag_underflow(self) -> bool { return self.0 & crate::C1_FLAG_UNDERFLOW!() != 0; }
+        pub fn flag_overflow(self) -> bool { return self.0 & crate::C1_FLAG_OVERFLOW!() != 0; }
+        pub fn flag_div_by_zero(self) -> bool { return self.0 & crate::C1_FLAG_DIV_BY_0!() != 0; }
+        pub fn flag_invalid(self) -> bool { return self.0 & crate::C1_FLAG_INVALID_OP!() != 0; }
+
+        pub fn enable_inexact(self) -> bool { return self.0 & crate::C1_ENABLE_INEXACT_OP!() != 0; }
+        pub fn enable_underflow(self) -> bool { return self.0 & crate::C1_ENABLE_UNDERFLOW!() != 0; }
+        pub fn enable_overflow(self) -> bool { return self.0 & crate::C1_ENABLE_OVERFLOW!() != 0; }
+        pub fn enable_div_by_zero(self) -> bool { return self.0 & crate::C1_ENABLE_DIV_BY_0!() != 0; }
+        pub fn enable_invalid(self) -> bool { return self.0 & crate::C1_ENABLE_INVALID_OP!() != 0; }
+
+        pub fn cause_inexact(self) -> bool { return self.0 & crate::C1_CAUSE_INEXACT_OP!() != 0; }
+        pub fn cause_underflow(self) -> bool { return self.0 & crate::C1_CAUSE_UNDERFLOW!() != 0; }
+        pub fn cause_overflow(self) -> bool { return self.0 & crate::C1_CAUSE_OVERFLOW!() != 0; }
+        pub fn cause_div_by_zero(self) -> bool { return self.0 & crate::C1_CAUSE_DIV_BY_0!() != 0; }
+        pub fn cause_invalid(self) -> bool { return self.0 & crate::C1_CAUSE_INVALID_OP!() != 0; }
+        pub fn cause_not_implemented(self) -> bool { return self.0 & crate::C1_CAUSE_NOT_IMPLEMENTED!() != 0; }
+
+        /// Return a copy with the given C1_FLAG_*, C1_ENABLE_* or C1_CAUSE_* bits set or cleared.
+        pub fn with_bits(self, mask: u32, set: bool) -> Fcr31 {
+            if set {
+                return Fcr31(self.0 | mask);
+            }
+
+            return Fcr31(self.0 & !mask);
+        }
+
+        pub fn with_enable_inexact(self, set: bool) -> Fcr31 { return self.with_bits(crate::C1_ENABLE_INEXACT_OP!(), set); }
+        pub fn with_enable_underflow(self, set: bool) -> Fcr31 { return self.with_bits(crate::C1_ENABLE_UNDERFLOW!(), set); }
+        pub fn with_enable_overflow(self, set: bool) -> Fcr31 { return self.with_bits(crate::C1_ENABLE_OVERFLOW!(), set); }
+        pub fn with_enable_div_by_zero(self, set: bool) -> Fcr31 { return self.with_bits(crate::C1_ENABLE_DIV_BY_0!(), set); }
+        pub fn with_enable_invalid(self, set: bool) -> Fcr31 { return self.with_bits(crate::C1_ENABLE_INVALID_OP!(), set); }
+
+        /// Return a copy with all cause bits (12-17) cleared.
+        ///
+        /// This is what a floating-point exception handler must write back to
+        /// keep the same exception from re-triggering.
+        pub fn clear_cause_bits(self) -> Fcr31 {
+            return Fcr31(self.0 & !C1_CAUSE_MASK);
+        }
+
+        /// Return a copy with all sticky flag bits (2-6) cleared.
+        pub fn clear_flag_bits(self) -> Fcr31 {
+            return Fcr31(self.0 & !C1_FLAG_MASK);
+        }
+
+        /// Return the raw enable bits (7-11).
+        pub fn enable_bits(self) -> u32 {
+            return self.0 & C1_ENABLE_MASK;
+        }
+
+        /// Current rounding mode.
+        pub fn rounding_mode(self) -> RoundingMode {
+            return match self.0 & C1_ROUNDING_MODE_MASK {
+                0 => RoundingMode::Nearest,
+                1 => RoundingMode::Zero,
+                2 => RoundingMode::PlusInfinity,
+                _ => RoundingMode::MinusInfinity
+            };
+        }
+
+        /// Return a copy using the given rounding mode.
+        pub fn with_rounding_mode(self, mode: RoundingMode) -> Fcr31 {
+            return Fcr31((self.0 & !C1_ROUNDING_MODE_MASK) | mode as u32);
+        }
+    }
+
     /// Read the COP1 FCR31 register (floating-point control register 31)
     ///
     /// FCR31 is also known as the Control/Status register.
@@ -2028,6 +4630,83 @@ pub mod COP1 {
     pub fn WRITE_FCR31(x: u32) {
         crate::C1_WRITE_FCR31!(x);
     }
+
+    /// Set of floating-point exception enable bits, built from the C1_ENABLE_* values.
+    ///
+    /// Sets combine with |, e.g. Fcr31Flags::OVERFLOW | Fcr31Flags::DIV_BY_0.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Fcr31Flags(u32);
+
+    impl Fcr31Flags {
+        pub const INEXACT_OP: Fcr31Flags = Fcr31Flags(crate::C1_ENABLE_INEXACT_OP!());
+        pub const UNDERFLOW: Fcr31Flags = Fcr31Flags(crate::C1_ENABLE_UNDERFLOW!());
+        pub const OVERFLOW: Fcr31Flags = Fcr31Flags(crate::C1_ENABLE_OVERFLOW!());
+        pub const DIV_BY_0: Fcr31Flags = Fcr31Flags(crate::C1_ENABLE_DIV_BY_0!());
+        pub const INVALID_OP: Fcr31Flags = Fcr31Flags(crate::C1_ENABLE_INVALID_OP!());
+
+        /// The empty set.
+        pub const fn empty() -> Fcr31Flags {
+            return Fcr31Flags(0);
+        }
+
+        /// Every exception enable bit.
+        pub const fn all() -> Fcr31Flags {
+            return Fcr31Flags(C1_ENABLE_MASK);
+        }
+
+        /// Build a set from raw C1_ENABLE_* bits, dropping anything else.
+        pub const fn from_bits_truncate(bits: u32) -> Fcr31Flags {
+            return Fcr31Flags(bits & C1_ENABLE_MASK);
+        }
+
+        /// Return the raw C1_ENABLE_* bits.
+        pub const fn bits(self) -> u32 {
+            return self.0;
+        }
+
+        /// Return whether every bit in other is also in this set.
+        pub const fn contains(self, other: Fcr31Flags) -> bool {
+            return self.0 & other.0 == other.0;
+        }
+
+        /// Return whether the set is empty.
+        pub const fn is_empty(self) -> bool {
+            return self.0 == 0;
+        }
+    }
+
+    impl core::ops::BitOr for Fcr31Flags {
+        type Output = Fcr31Flags;
+
+        fn bitor(self, rhs: Fcr31Flags) -> Fcr31Flags {
+            return Fcr31Flags(self.0 | rhs.0);
+        }
+    }
+
+    /// Enable trapping for the given floating-point exceptions.
+    ///
+    /// Performs a read-modify-write of FCR31, leaving other enable bits untouched.
+    /// Stale cause bits are cleared in the same write, since setting an enable
+    /// bit whose cause bit is already set traps immediately (Inexact's almost
+    /// always is). Pair this with an exception handler that clears the cause
+    /// bits, otherwise the first trapped exception will re-trigger forever.
+    pub fn enable_exceptions(mask: Fcr31Flags) {
+        let fcr31 = FCR31();
+        WRITE_FCR31((fcr31 & !C1_CAUSE_MASK) | mask.bits());
+    }
+
+    /// Disable trapping for the given floating-point exceptions.
+    ///
+    /// Performs a read-modify-write of FCR31, leaving other enable bits untouched.
+    pub fn disable_exceptions(mask: Fcr31Flags) {
+        let fcr31 = FCR31();
+        WRITE_FCR31(fcr31 & !mask.bits());
+    }
+
+    /// Return the set of floating-point exceptions that currently trap.
+    pub fn exceptions_enabled() -> Fcr31Flags {
+        return Fcr31Flags::from_bits_truncate(FCR31());
+    }
 }
 
 /// Interface to the hardware sprite/triangle rasterizer (RDP).
@@ -2068,7 +4747,9 @@ pub mod COP1 {
 /// be enabled for proper operation. This also means that code should under normal
 /// circumstances never use SYNC_FULL.
 pub mod RDP {
-    use crate::{Display::DisplayContext, GraphicsEngine::{N64Color, Sprite}, bindings};
+    use core::sync::atomic::{AtomicBool, AtomicU32, Ordering};
+
+    use crate::{Display::{self, BitDepth, DisplayContext}, GraphicsEngine::{self, N64Color, RGBColor, Sprite}, Interrupt::{self, InterruptState}, Rect, bindings};
 
     /// RDP sync operations.
     #[repr(C)]
@@ -2095,8 +4776,137 @@ pub mod RDP {
         FLUSH_STRATEGY_AUTOMATIC // Cache will be flushed on all incoming textures.
     }
 
+    /// A triangle vertex in screen coordinates.
+    #[derive(Clone, Copy, Debug, PartialEq)]
+    pub struct Vertex {
+        pub x: f32,
+        pub y: f32
+    }
+
+    impl Vertex {
+        pub const fn new(x: f32, y: f32) -> Vertex {
+            return Vertex { x, y };
+        }
+    }
+
+    /// Fill modes selected by set_mode().
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum FillMode {
+        Primitive = 1,  // Filled rectangles, see enable_primitive_fill()
+        Blend = 2,      // Filled triangles, see enable_blend_fill()
+        TextureCopy = 3 // Sprites and textured rectangles, see enable_texture_copy()
+    }
+
+    /// Errors returned by attach().
+    #[derive(Debug)]
+    pub enum RdpError {
+        InterruptsDisabled, // detach_display() needs interrupts enabled to complete
+        AlreadyAttached     // Another RdpFrame is still alive
+    }
+
+    static ATTACHED: AtomicBool = AtomicBool::new(false);
+
+    /// Number of texture slots libdragon tracks (slot numbers are masked to 0-7).
+    const TEXTURE_SLOTS: usize = 8;
+
+    const GENERATION_INIT: AtomicU32 = AtomicU32::new(0);
+    static SLOT_GENERATION: [AtomicU32; TEXTURE_SLOTS] = [GENERATION_INIT; TEXTURE_SLOTS];
+
+    /// Record a new load into a slot, invalidating older handles to it.
+    fn claim_slot(tex_slot: u32) -> u32 {
+        return SLOT_GENERATION[tex_slot as usize % TEXTURE_SLOTS].fetch_add(1, Ordering::Relaxed) + 1;
+    }
+
+    // Clipping boundary set through this module, valid while CLIPPING_SET is true.
+    const BOUND_INIT: AtomicU32 = AtomicU32::new(0);
+    static CLIPPING: [AtomicU32; 4] = [BOUND_INIT; 4];
+    static CLIPPING_SET: AtomicBool = AtomicBool::new(false);
+
+    // The boundary set through this module, None for the full screen.
+    fn current_clipping() -> Option<[u32; 4]> {
+        if !CLIPPING_SET.load(Ordering::Acquire) {
+            return None;
+        }
+
+        return Some([
+            CLIPPING[0].load(Ordering::Relaxed),
+            CLIPPING[1].load(Ordering::Relaxed),
+            CLIPPING[2].load(Ordering::Relaxed),
+            CLIPPING[3].load(Ordering::Relaxed)
+        ]);
+    }
+
+    // Current FillMode as set through this module, 0 if none.
+    static MODE: AtomicU32 = AtomicU32::new(0);
+
+    // Work queued on the RDP since the matching sync, tracked by the wrappers in this module.
+    const PENDING_PIPE: u32 = 0x1; // A primitive was drawn since the last SYNC_PIPE
+    const PENDING_LOAD: u32 = 0x2; // A texture was loaded since the last SYNC_LOAD
+
+    static SYNC_PENDING: AtomicU32 = AtomicU32::new(0);
+
+    fn mark_pending(pending: u32) {
+        SYNC_PENDING.fetch_or(pending, Ordering::Relaxed);
+    }
+
+    /// A texture loaded into RDP TMEM by load() or load_stride().
+    ///
+    /// Drawing with a handle whose slot has since been loaded with something else
+    /// panics instead of drawing whatever now occupies the slot.
+    #[derive(Clone, Copy, Debug)]
+    pub struct TextureSlot {
+        slot: u32,
+        width: u32,
+        height: u32,
+        bytes: u32,
+        generation: u32
+    }
+
+    impl TextureSlot {
+        fn current(slot: u32, width: u32, height: u32, bytes: u32) -> TextureSlot {
+            let generation = SLOT_GENERATION[slot as usize % TEXTURE_SLOTS].load(Ordering::Relaxed);
+
+            return TextureSlot { slot, width, height, bytes, generation };
+        }
+
+        /// The RDP texture slot the texture was loaded into.
+        pub fn slot(&self) -> u32 {
+            return self.slot;
+        }
+
+        /// Width in pixels of the loaded texture.
+        pub fn width(&self) -> u32 {
+            return self.width;
+        }
+
+        /// Height in pixels of the loaded texture.
+        pub fn height(&self) -> u32 {
+            return self.height;
+        }
+
+        /// Number of bytes consumed in RDP TMEM by the load.
+        pub fn tmem_bytes(&self) -> u32 {
+            return self.bytes;
+        }
+
+        /// Return whether the slot still holds this texture.
+        pub fn is_current(&self) -> bool {
+            return SLOT_GENERATION[self.slot as usize % TEXTURE_SLOTS].load(Ordering::Relaxed) == self.generation;
+        }
+
+        fn checked_slot(&self) -> u32 {
+            if !self.is_current() {
+                panic!("RDP texture slot {} was overwritten by a later load", self.slot);
+            }
+
+            return self.slot;
+        }
+    }
+
     /// Initialize the RDP system.
     pub fn init() {
+        MODE.store(0, Ordering::Relaxed);
+
         unsafe { bindings::rdp_init(); }
     }
 
@@ -2106,6 +4916,8 @@ pub mod RDP {
     /// Display::lock(). This should be performed before any other operations to
     /// ensure that the RDP has a valid output buffer to operate on.
     pub fn attach_display(disp: DisplayContext) {
+        SYNC_PENDING.store(0, Ordering::Relaxed);
+
         unsafe { bindings::rdp_attach_display(disp); }
     }
 
@@ -2128,30 +4940,101 @@ pub mod RDP {
     /// rule of thumb is to only add a sync operation if the data you need is not yet
     /// available in the pipeline.
     pub fn sync(sync: Sync) {
+        let cleared = match sync {
+            Sync::SYNC_FULL => PENDING_PIPE | PENDING_LOAD,
+            Sync::SYNC_PIPE => PENDING_PIPE,
+            Sync::SYNC_LOAD => PENDING_LOAD,
+            Sync::SYNC_TILE => 0
+        };
+        SYNC_PENDING.fetch_and(!cleared, Ordering::Relaxed);
+
         unsafe { bindings::rdp_sync(sync); }
     }
 
+    /// Perform a SYNC_PIPE only if one is needed.
+    ///
+    /// Call this before clipping changes, fill/texture mode changes or a new texture
+    /// load. The sync is only emitted if a rectangle, triangle or sprite has been
+    /// drawn through this module since the last SYNC_PIPE (or SYNC_FULL), so calling
+    /// it defensively doesn't stall the pipeline with redundant syncs. Attaching a
+    /// display resets the tracking. Commands issued behind the module's back are not
+    /// seen, in which case sync() can still be called manually.
+    pub fn ensure_pipe_sync() {
+        if SYNC_PENDING.load(Ordering::Relaxed) & PENDING_PIPE != 0 {
+            sync(Sync::SYNC_PIPE);
+        }
+    }
+
+    /// Perform a SYNC_LOAD only if one is needed.
+    ///
+    /// The sync is only emitted if a texture has been loaded through this module since
+    /// the last SYNC_LOAD (or SYNC_FULL). See ensure_pipe_sync() for the limits of the
+    /// tracking.
+    pub fn ensure_load_sync() {
+        if SYNC_PENDING.load(Ordering::Relaxed) & PENDING_LOAD != 0 {
+            sync(Sync::SYNC_LOAD);
+        }
+    }
+
     /// Set the hardware clipping boundary.
     pub fn set_clipping(top_left_x: u32, top_left_y: u32, bottom_right_x: u32, bottom_right_y: u32) {
+        for (bound, value) in CLIPPING.iter().zip([top_left_x, top_left_y, bottom_right_x, bottom_right_y].iter()) {
+            bound.store(*value, Ordering::Relaxed);
+        }
+        CLIPPING_SET.store(true, Ordering::Release);
+
         unsafe { bindings::rdp_set_clipping(top_left_x, top_left_y, bottom_right_x, bottom_right_y); }
     }
 
     /// Set the hardware clipping boundary to the entire screen.
     pub fn set_default_clipping() {
+        CLIPPING_SET.store(false, Ordering::Release);
+
         unsafe { bindings::rdp_set_default_clipping(); }
     }
 
-    /// Enable display of 2D filled (untextured) rectangles.
+    /// Clip drawing to a rectangle for the duration of a closure.
     ///
-    /// This must be called before using draw_filled_rectangle().
-    pub fn enable_primitive_fill() {
-        unsafe { bindings::rdp_enable_primitive_fill(); }
+    /// Sets the clipping boundary to rect, runs f, then restores whatever clipping
+    /// was active before: the enclosing with_clipping() rectangle when nested, or
+    /// the full screen otherwise. A SYNC_PIPE is issued before each change if needed.
+    ///
+    /// rect is intersected with the screen first (see GraphicsEngine::screen_rect()),
+    /// so parts of it off the screen are dropped; if nothing is left, nothing is drawn.
+    pub fn with_clipping<R>(rect: Rect, f: impl FnOnce() -> R) -> R {
+        let outer = current_clipping();
+        let clip = GraphicsEngine::screen_rect().intersection(&rect).unwrap_or(Rect::new(0, 0, 0, 0));
+        let (x, y) = (clip.x as u32, clip.y as u32);
+
+        ensure_pipe_sync();
+        set_clipping(x, y, x + clip.w as u32, y + clip.h as u32);
+
+        let result = f();
+
+        ensure_pipe_sync();
+        match outer {
+            Some([top_left_x, top_left_y, bottom_right_x, bottom_right_y]) => set_clipping(top_left_x, top_left_y, bottom_right_x, bottom_right_y),
+            None => set_default_clipping()
+        }
+
+        return result;
+    }
+
+    /// Enable display of 2D filled (untextured) rectangles.
+    ///
+    /// This must be called before using draw_filled_rectangle().
+    pub fn enable_primitive_fill() {
+        MODE.store(FillMode::Primitive as u32, Ordering::Relaxed);
+
+        unsafe { bindings::rdp_enable_primitive_fill(); }
     }
 
     /// Enable display of 2D filled (untextured) triangles.
     ///
     /// This must be called before using draw_filled_triangle().
     pub fn enable_blend_fill() {
+        MODE.store(FillMode::Blend as u32, Ordering::Relaxed);
+
         unsafe { bindings::rdp_enable_blend_fill(); }
     }
 
@@ -2160,13 +5043,47 @@ pub mod RDP {
     /// This must be called before using draw_textured_rectangle_scaled(),
     /// draw_textured_rectangle(), draw_sprite() or draw_sprite_scaled().
     pub fn enable_texture_copy() {
+        MODE.store(FillMode::TextureCopy as u32, Ordering::Relaxed);
+
         unsafe { bindings::rdp_enable_texture_copy(); }
     }
 
+    /// Switch the RDP to the given fill mode.
+    ///
+    /// Equivalent to calling enable_primitive_fill(), enable_blend_fill() or
+    /// enable_texture_copy(). The draw methods on RdpFrame check the mode set
+    /// here and panic if it doesn't match the primitive being drawn.
+    pub fn set_mode(mode: FillMode) {
+        match mode {
+            FillMode::Primitive => enable_primitive_fill(),
+            FillMode::Blend => enable_blend_fill(),
+            FillMode::TextureCopy => enable_texture_copy()
+        }
+    }
+
+    /// Return the fill mode last set through this module, if any.
+    pub fn mode() -> Option<FillMode> {
+        return match MODE.load(Ordering::Relaxed) {
+            1 => Some(FillMode::Primitive),
+            2 => Some(FillMode::Blend),
+            3 => Some(FillMode::TextureCopy),
+            _ => None
+        };
+    }
+
+    fn require_mode(mode: FillMode, operation: &str) {
+        if self::mode() != Some(mode) {
+            panic!("RDP::{} requires {:?} mode, call set_mode() first", operation, mode);
+        }
+    }
+
     /// Load a sprite into RDP TMEM.
     ///
     /// Returns: number of bytes consumed in RDP TMEM by loading this sprite
     pub fn load_texture(tex_slot: u32, tex_location: u32, mirror: Mirror, sprite: &mut Sprite) -> u32 {
+        claim_slot(tex_slot);
+        mark_pending(PENDING_LOAD);
+
         unsafe { return bindings::rdp_load_texture(tex_slot, tex_location, mirror, sprite); }
     }
 
@@ -2178,9 +5095,35 @@ pub mod RDP {
     ///
     /// Returns: number of bytes consumed in RDP TMEM by loading this sprite
     pub fn load_texture_stride(tex_slot: u32, tex_location: u32, mirror: Mirror, sprite: &mut Sprite, offset: i32) -> u32 {
+        claim_slot(tex_slot);
+        mark_pending(PENDING_LOAD);
+
         unsafe { return bindings::rdp_load_texture_stride(tex_slot, tex_location, mirror, sprite, offset); }
     }
 
+    /// Load a sprite into RDP TMEM and return a handle to the loaded texture.
+    ///
+    /// The handle remembers the slot and sprite dimensions and is what the draw
+    /// functions take. Loading anything else into the same slot invalidates it.
+    pub fn load(tex_slot: u32, tex_location: u32, mirror: Mirror, sprite: &mut Sprite) -> TextureSlot {
+        let width = sprite.width as u32;
+        let height = sprite.height as u32;
+        let bytes = load_texture(tex_slot, tex_location, mirror, sprite);
+
+        return TextureSlot::current(tex_slot, width, height, bytes);
+    }
+
+    /// Load part of a spritemap into RDP TMEM and return a handle to the loaded texture.
+    ///
+    /// The handle's dimensions are those of a single slice of the spritemap.
+    pub fn load_stride(tex_slot: u32, tex_location: u32, mirror: Mirror, sprite: &mut Sprite, offset: i32) -> TextureSlot {
+        let width = sprite.width as u32 / core::cmp::max(sprite.hslices as u32, 1);
+        let height = sprite.height as u32 / core::cmp::max(sprite.vslices as u32, 1);
+        let bytes = load_texture_stride(tex_slot, tex_location, mirror, sprite, offset);
+
+        return TextureSlot::current(tex_slot, width, height, bytes);
+    }
+
     /// Draw a textured rectangle.
     ///
     /// Given an already loaded texture, this function will draw a rectangle textured
@@ -2189,8 +5132,10 @@ pub mod RDP {
     ///
     /// Before using this command to draw a textured rectangle, use enable_texture_copy()
     /// to set the RDP up in texture mode.
-    pub fn draw_textured_rectangle(tex_slot: u32, top_left_x: i32, top_left_y: i32, bottom_right_x: i32, bottom_right_y: i32, mirror: Mirror) {
-        unsafe { bindings::rdp_draw_textured_rectangle(tex_slot, top_left_x, top_left_y, bottom_right_x, bottom_right_y, mirror); }
+    pub fn draw_textured_rectangle(slot: &TextureSlot, top_left_x: i32, top_left_y: i32, bottom_right_x: i32, bottom_right_y: i32, mirror: Mirror) {
+        mark_pending(PENDING_PIPE);
+
+        unsafe { bindings::rdp_draw_textured_rectangle(slot.checked_slot(), top_left_x, top_left_y, bottom_right_x, bottom_right_y, mirror); }
     }
 
     /// Draw a textured rectangle with a scaled texture.
@@ -2203,8 +5148,10 @@ pub mod RDP {
     ///
     /// Before using this command to draw a textured rectangle, use enable_texture_copy()
     /// to set the RDP up in texture mode.
-    pub fn draw_textured_rectangle_scaled(tex_slot: u32, top_left_x: i32, top_left_y: i32, bottom_right_x: i32, bottom_right_y: i32, x_scale: f64, y_scale: f64, mirror: Mirror) {
-        unsafe { bindings::rdp_draw_textured_rectangle_scaled(tex_slot, top_left_x, top_left_y, bottom_right_x, bottom_right_y, x_scale, y_scale, mirror); }
+    pub fn draw_textured_rectangle_scaled(slot: &TextureSlot, top_left_x: i32, top_left_y: i32, bottom_right_x: i32, bottom_right_y: i32, x_scale: f64, y_scale: f64, mirror: Mirror) {
+        mark_pending(PENDING_PIPE);
+
+        unsafe { bindings::rdp_draw_textured_rectangle_scaled(slot.checked_slot(), top_left_x, top_left_y, bottom_right_x, bottom_right_y, x_scale, y_scale, mirror); }
     }
 
     /// Draw a texture to the screen as a sprite.
@@ -2214,8 +5161,10 @@ pub mod RDP {
     ///
     /// Before using this command to draw a textured rectangle, use enable_texture_copy()
     /// to set the RDP up in texture mode.
-    pub fn draw_sprite(tex_slot: u32, top_left_x: i32, top_left_y: i32, mirror: Mirror) {
-        unsafe { bindings::rdp_draw_sprite(tex_slot, top_left_x, top_left_y, mirror); }
+    pub fn draw_sprite(slot: &TextureSlot, top_left_x: i32, top_left_y: i32, mirror: Mirror) {
+        mark_pending(PENDING_PIPE);
+
+        unsafe { bindings::rdp_draw_sprite(slot.checked_slot(), top_left_x, top_left_y, mirror); }
     }
 
     /// Draw a texture to the screen as a scaled sprite.
@@ -2225,8 +5174,29 @@ pub mod RDP {
     ///
     /// Before using this command to draw a textured rectangle, use enable_texture_copy()
     /// to set the RDP up in texture mode.
-    pub fn draw_sprite_scaled(tex_slot: u32, top_left_x: i32, top_left_y: i32, x_scale: f64, y_scale: f64, mirror: Mirror) {
-        unsafe { bindings::rdp_draw_sprite_scaled(tex_slot, top_left_x, top_left_y, x_scale, y_scale, mirror); }
+    pub fn draw_sprite_scaled(slot: &TextureSlot, top_left_x: i32, top_left_y: i32, x_scale: f64, y_scale: f64, mirror: Mirror) {
+        mark_pending(PENDING_PIPE);
+
+        unsafe { bindings::rdp_draw_sprite_scaled(slot.checked_slot(), top_left_x, top_left_y, x_scale, y_scale, mirror); }
+    }
+
+    /// Draw a loaded texture stretched to fill a destination rectangle.
+    ///
+    /// Computes the x/y scale from the texture dimensions remembered by the slot
+    /// handle and calls draw_sprite_scaled(). Nothing is drawn if either the texture
+    /// or the destination has no area, rather than dividing by zero.
+    ///
+    /// Before using this command, use enable_texture_copy() to set the RDP up in
+    /// texture mode.
+    pub fn draw_sprite_to_rect(slot: &TextureSlot, dst: Rect, mirror: Mirror) {
+        if slot.width() == 0 || slot.height() == 0 || dst.w <= 0 || dst.h <= 0 {
+            return;
+        }
+
+        let x_scale = dst.w as f64 / slot.width() as f64;
+        let y_scale = dst.h as f64 / slot.height() as f64;
+
+        draw_sprite_scaled(slot, dst.x, dst.y, x_scale, y_scale, mirror);
     }
 
     /// Set the primitive draw color for subsequent filled primitive operations.
@@ -2239,6 +5209,40 @@ pub mod RDP {
         unsafe { bindings::rdp_set_primitive_color(color); }
     }
 
+    /// Set the primitive draw color from an RGBColor.
+    ///
+    /// Packs the color for the current Display::bit_depth(): in 16 bpp mode the
+    /// 5-5-5-1 color is duplicated into both halves of the word, as the RDP fills
+    /// two pixels at a time. This avoids the color only showing on every other
+    /// pixel column when a 32-bit color is used on a 16 bpp display.
+    pub fn set_primitive_color_rgb(color: RGBColor) {
+        let packed = match Display::bit_depth() {
+            BitDepth::DEPTH_16_BPP => {
+                let rgba16 = pack_rgba16(&color);
+                rgba16 << 16 | rgba16
+            },
+            BitDepth::DEPTH_32_BPP => pack_rgba32(&color)
+        };
+
+        set_primitive_color(packed);
+    }
+
+    /// Set the blend draw color from an RGBColor.
+    ///
+    /// The blend color register always holds an 8-8-8-8 color regardless of the
+    /// framebuffer depth, so the color is never packed as 16-bit.
+    pub fn set_blend_color_rgb(color: RGBColor) {
+        set_blend_color(pack_rgba32(&color));
+    }
+
+    fn pack_rgba32(color: &RGBColor) -> N64Color {
+        return (color.r as u32) << 24 | (color.g as u32) << 16 | (color.b as u32) << 8 | color.a as u32;
+    }
+
+    fn pack_rgba16(color: &RGBColor) -> N64Color {
+        return (color.r as u32 >> 3) << 11 | (color.g as u32 >> 3) << 6 | (color.b as u32 >> 3) << 1 | color.a as u32 >> 7;
+    }
+
     /// Set the blend draw color for subsequent filled primitive operations.
     ///
     /// This function sets the color of all draw_filled_triangle() operations that follow.
@@ -2256,6 +5260,8 @@ pub mod RDP {
     /// Before calling this function, make sure that the RDP is set to primitive mode by
     /// calling enable_primitive_fill().
     pub fn draw_filled_rectangle(top_left_x: i32, top_left_y: i32, bottom_right_x: i32, bottom_right_y: i32) {
+        mark_pending(PENDING_PIPE);
+
         unsafe { bindings::rdp_draw_filled_rectangle(top_left_x, top_left_y, bottom_right_x, bottom_right_y); }
     }
 
@@ -2267,9 +5273,52 @@ pub mod RDP {
     /// Before calling this function, make sure that the RDP is set to blend mode by
     /// calling enable_blend_fill().
     pub fn draw_filled_triangle(x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32) {
+        mark_pending(PENDING_PIPE);
+
         unsafe { bindings::rdp_draw_filled_triangle(x1, y1, x2, y2, x3, y3); }
     }
 
+    /// Draw a filled triangle from three vertices.
+    ///
+    /// Same as draw_filled_triangle(), with the coordinates grouped per vertex.
+    /// Before calling this function, make sure that the RDP is set to blend mode
+    /// by calling enable_blend_fill().
+    pub fn draw_triangle(v0: Vertex, v1: Vertex, v2: Vertex) {
+        draw_filled_triangle(v0.x, v0.y, v1.x, v1.y, v2.x, v2.y);
+    }
+
+    /// Draw a batch of filled triangles.
+    ///
+    /// Syncs the pipeline if needed and enables blend fill once before drawing
+    /// every triangle in the color set with set_blend_color().
+    pub fn draw_triangles(triangles: &[[Vertex; 3]]) {
+        ensure_pipe_sync();
+        enable_blend_fill();
+
+        for [v0, v1, v2] in triangles {
+            draw_triangle(*v0, *v1, *v2);
+        }
+    }
+
+    /// Draw a batch of filled rectangles in a single color.
+    ///
+    /// Syncs the pipeline if needed, enables primitive fill and sets the primitive
+    /// color once, then draws every rectangle. This avoids a mode switch per
+    /// rectangle when clearing regions or drawing solid UI/tilemap blocks. Each
+    /// rectangle is drawn from (x, y) to (x + w, y + h).
+    pub fn fill_rects(color: N64Color, rects: &[Rect]) {
+        ensure_pipe_sync();
+        enable_primitive_fill();
+        set_primitive_color(color);
+
+        for rect in rects {
+            let (top_left_x, top_left_y) = rect.top_left();
+            let (bottom_right_x, bottom_right_y) = rect.bottom_right();
+
+            draw_filled_rectangle(top_left_x, top_left_y, bottom_right_x, bottom_right_y);
+        }
+    }
+
     /// Set the flush strategy for texture loads.
     ///
     /// If textures are guaranteed to be in uncached RDRAM or the cache is flushed before
@@ -2287,236 +5336,2102 @@ pub mod RDP {
     pub fn close() {
         unsafe { bindings::rdp_close(); }
     }
-}
 
-/// Hardware Vector Interface.
-pub mod RSP {
-    use cty::*;
+    /// Attach the RDP to a display context for the lifetime of the returned frame.
+    ///
+    /// The frame calls detach_display() when dropped, so every attach is matched by
+    /// a detach even on an early return. Because detaching waits on the DP interrupt,
+    /// this fails if interrupts are not currently enabled. Only one frame may be
+    /// alive at a time.
+    pub fn attach(disp: DisplayContext) -> Result<RdpFrame, RdpError> {
+        match Interrupt::get_interrupts_state() {
+            InterruptState::INTERRUPTS_ENABLED => {},
+            _ => return Err(RdpError::InterruptsDisabled)
+        }
 
-    use crate::bindings;
+        if ATTACHED.swap(true, Ordering::Acquire) {
+            return Err(RdpError::AlreadyAttached);
+        }
 
-    ///
-    pub fn init() {
-        unsafe { bindings::rsp_init(); }
-    }
+        attach_display(disp);
 
-    ///
-    pub fn load_microcode(start: &mut c_void, size: c_ulong) {
-        unsafe { bindings::load_ucode(start, size); }
+        return Ok(RdpFrame { disp });
     }
 
+    /// An RDP attached to a display context, created by attach().
     ///
-    pub fn read_microcode(start: &mut c_void, size: c_ulong) {
-        unsafe { bindings::read_ucode(start, size); }
+    /// Drawing through the frame guarantees the RDP is attached, and each draw method
+    /// panics if the RDP isn't in the FillMode it needs. Dropping the frame detaches
+    /// the display, after which it is safe to call Display::show().
+    pub struct RdpFrame {
+        disp: DisplayContext
     }
 
-    ///
-    pub fn load_data(start: &mut c_void, size: c_ulong) {
-        unsafe { bindings::load_data(start, size); }
-    }
+    impl RdpFrame {
+        /// The display context this frame is drawing to.
+        pub fn display(&self) -> DisplayContext {
+            return self.disp;
+        }
 
-    ///
-    pub fn read_data(start: &mut c_void, size: c_ulong) {
-        unsafe { bindings::read_data(start, size); }
+        /// Perform a sync operation. See sync().
+        pub fn sync(&self, sync_op: Sync) {
+            sync(sync_op);
+        }
+
+        /// Perform a SYNC_PIPE only if one is needed. See ensure_pipe_sync().
+        pub fn ensure_pipe_sync(&self) {
+            ensure_pipe_sync();
+        }
+
+        /// Perform a SYNC_LOAD only if one is needed. See ensure_load_sync().
+        pub fn ensure_load_sync(&self) {
+            ensure_load_sync();
+        }
+
+        /// Set the hardware clipping boundary. See set_clipping().
+        pub fn set_clipping(&self, top_left_x: u32, top_left_y: u32, bottom_right_x: u32, bottom_right_y: u32) {
+            set_clipping(top_left_x, top_left_y, bottom_right_x, bottom_right_y);
+        }
+
+        /// Set the hardware clipping boundary to the entire screen.
+        pub fn set_default_clipping(&self) {
+            set_default_clipping();
+        }
+
+        /// Clip drawing to a rectangle for the duration of a closure. See with_clipping().
+        pub fn with_clipping<R>(&self, rect: Rect, f: impl FnOnce() -> R) -> R {
+            return with_clipping(rect, f);
+        }
+
+        /// Enable display of 2D filled (untextured) rectangles.
+        pub fn enable_primitive_fill(&self) {
+            enable_primitive_fill();
+        }
+
+        /// Enable display of 2D filled (untextured) triangles.
+        pub fn enable_blend_fill(&self) {
+            enable_blend_fill();
+        }
+
+        /// Enable display of 2D sprites.
+        pub fn enable_texture_copy(&self) {
+            enable_texture_copy();
+        }
+
+        /// Switch the RDP to the given fill mode. See set_mode().
+        pub fn set_mode(&self, mode: FillMode) {
+            set_mode(mode);
+        }
+
+        /// Load a sprite into RDP TMEM. See load_texture().
+        pub fn load_texture(&self, tex_slot: u32, tex_location: u32, mirror: Mirror, sprite: &mut Sprite) -> u32 {
+            return load_texture(tex_slot, tex_location, mirror, sprite);
+        }
+
+        /// Load part of a sprite into RDP TMEM. See load_texture_stride().
+        pub fn load_texture_stride(&self, tex_slot: u32, tex_location: u32, mirror: Mirror, sprite: &mut Sprite, offset: i32) -> u32 {
+            return load_texture_stride(tex_slot, tex_location, mirror, sprite, offset);
+        }
+
+        /// Load a sprite into RDP TMEM and return a handle to it. See load().
+        pub fn load(&self, tex_slot: u32, tex_location: u32, mirror: Mirror, sprite: &mut Sprite) -> TextureSlot {
+            return load(tex_slot, tex_location, mirror, sprite);
+        }
+
+        /// Load part of a spritemap into RDP TMEM and return a handle to it. See load_stride().
+        pub fn load_stride(&self, tex_slot: u32, tex_location: u32, mirror: Mirror, sprite: &mut Sprite, offset: i32) -> TextureSlot {
+            return load_stride(tex_slot, tex_location, mirror, sprite, offset);
+        }
+
+        /// Draw a textured rectangle. See draw_textured_rectangle().
+        pub fn draw_textured_rectangle(&self, slot: &TextureSlot, top_left_x: i32, top_left_y: i32, bottom_right_x: i32, bottom_right_y: i32, mirror: Mirror) {
+            require_mode(FillMode::TextureCopy, "draw_textured_rectangle");
+            draw_textured_rectangle(slot, top_left_x, top_left_y, bottom_right_x, bottom_right_y, mirror);
+        }
+
+        /// Draw a textured rectangle with a scaled texture. See draw_textured_rectangle_scaled().
+        pub fn draw_textured_rectangle_scaled(&self, slot: &TextureSlot, top_left_x: i32, top_left_y: i32, bottom_right_x: i32, bottom_right_y: i32, x_scale: f64, y_scale: f64, mirror: Mirror) {
+            require_mode(FillMode::TextureCopy, "draw_textured_rectangle_scaled");
+            draw_textured_rectangle_scaled(slot, top_left_x, top_left_y, bottom_right_x, bottom_right_y, x_scale, y_scale, mirror);
+        }
+
+        /// Draw a texture to the screen as a sprite. See draw_sprite().
+        pub fn draw_sprite(&self, slot: &TextureSlot, top_left_x: i32, top_left_y: i32, mirror: Mirror) {
+            require_mode(FillMode::TextureCopy, "draw_sprite");
+            draw_sprite(slot, top_left_x, top_left_y, mirror);
+        }
+
+        /// Draw a texture to the screen as a scaled sprite. See draw_sprite_scaled().
+        pub fn draw_sprite_scaled(&self, slot: &TextureSlot, top_left_x: i32, top_left_y: i32, x_scale: f64, y_scale: f64, mirror: Mirror) {
+            require_mode(FillMode::TextureCopy, "draw_sprite_scaled");
+            draw_sprite_scaled(slot, top_left_x, top_left_y, x_scale, y_scale, mirror);
+        }
+
+        /// Draw a loaded texture stretched to fill a destination rectangle. See draw_sprite_to_rect().
+        pub fn draw_sprite_to_rect(&self, slot: &TextureSlot, dst: Rect, mirror: Mirror) {
+            require_mode(FillMode::TextureCopy, "draw_sprite_to_rect");
+            draw_sprite_to_rect(slot, dst, mirror);
+        }
+
+        /// Set the primitive draw color. See set_primitive_color().
+        pub fn set_primitive_color(&self, color: N64Color) {
+            set_primitive_color(color);
+        }
+
+        /// Set the primitive draw color from an RGBColor. See set_primitive_color_rgb().
+        pub fn set_primitive_color_rgb(&self, color: RGBColor) {
+            set_primitive_color_rgb(color);
+        }
+
+        /// Set the blend draw color from an RGBColor. See set_blend_color_rgb().
+        pub fn set_blend_color_rgb(&self, color: RGBColor) {
+            set_blend_color_rgb(color);
+        }
+
+        /// Set the blend draw color. See set_blend_color().
+        pub fn set_blend_color(&self, color: N64Color) {
+            set_blend_color(color);
+        }
+
+        /// Draw a filled rectangle. See draw_filled_rectangle().
+        pub fn draw_filled_rectangle(&self, top_left_x: i32, top_left_y: i32, bottom_right_x: i32, bottom_right_y: i32) {
+            require_mode(FillMode::Primitive, "draw_filled_rectangle");
+            draw_filled_rectangle(top_left_x, top_left_y, bottom_right_x, bottom_right_y);
+        }
+
+        /// Draw a filled triangle. See draw_filled_triangle().
+        pub fn draw_filled_triangle(&self, x1: f32, y1: f32, x2: f32, y2: f32, x3: f32, y3: f32) {
+            require_mode(FillMode::Blend, "draw_filled_triangle");
+            draw_filled_triangle(x1, y1, x2, y2, x3, y3);
+        }
+
+        /// Draw a filled triangle from three vertices. See draw_triangle().
+        pub fn draw_triangle(&self, v0: Vertex, v1: Vertex, v2: Vertex) {
+            require_mode(FillMode::Blend, "draw_triangle");
+            draw_triangle(v0, v1, v2);
+        }
+
+        /// Draw a batch of filled triangles. See draw_triangles().
+        pub fn draw_triangles(&self, triangles: &[[Vertex; 3]]) {
+            draw_triangles(triangles);
+        }
+
+        /// Draw a batch of filled rectangles in a single color. See fill_rects().
+        pub fn fill_rects(&self, color: N64Color, rects: &[Rect]) {
+            fill_rects(color, rects);
+        }
     }
 
-    ///
-    pub fn run_microcode() {
-        unsafe { bindings::run_ucode(); }
+    impl Drop for RdpFrame {
+        fn drop(&mut self) {
+            detach_display();
+            ATTACHED.store(false, Ordering::Release);
+        }
     }
+
+    /// Another name for RdpFrame, the guard returned by attach().
+    pub type AttachedDisplay = RdpFrame;
 }
 
-/// Interface to the timer module in the MIPS r4300 processor.
-///
-/// The timer subsystem allows code to receive a callback after a specified number
-/// of ticks or microseconds. It interfaces with the MIPS coprocessor 0 to handle
-/// the timer interrupt and provide useful timing services.
-///
-/// Before attempting to use the timer subsystem, code should call timer_init.
-/// After the timer subsystem has been initialized, a new one-shot or continuous
-/// timer can be created with new_timer(). To remove an expired one-shot timer or
-/// a recurring timer, use delete_timer(). To temporarily stop a timer, use stop_timer().
-/// To restart a stopped timer or an expired one-shot timer, use start_timer().
-/// Once code no longer needs the timer subsystem, a call to timer_close() will free
-/// all continuous timers and shut down the timer subsystem. Note that timers removed
-/// with stop_timer() or expired one-short timers will not be removed automatically
-/// and are the responsibility of the calling code to be freed, regardless of a call
-/// to timer_close().
-///
-/// Because the MIPS internal counter wraps around after ~90 seconds (see TICKS_READ),
-/// it's not possible to schedule a timer more than 90 seconds in the future.
-pub mod Timer {
+/// Hardware Vector Interface.
+pub mod RSP {
+    use core::sync::atomic::{AtomicBool, Ordering};
     use cty::*;
 
-    use crate::bindings;
+    use volatile::Volatile;
 
-    #[repr(C)]
-    pub struct TimerLink {
-        pub left: c_uint,
-        pub set: c_uint,
-        pub ovfl: c_int,
-        pub flags: c_int,
-        pub callback: extern "C" fn(ovfl: c_int),
-    }
+    use crate::{GraphicsEngine::Sprite, Interrupt::{self, InterruptState}, N64System, bindings};
+
+    /// Errors returned by the slice transfers and run_and_wait().
+    #[derive(Debug)]
+    pub enum RspError {
+        Unaligned,   // Buffer address or length is not a multiple of 8 bytes
+        InvalidSize, // Transfer is empty or larger than IMEM/DMEM
+        Timeout      // The microcode didn't halt in time; the RSP is left running (see halt())
+    }
+
+    // SP status register bits (read side).
+    const SP_STATUS_HALT: u32 = 1 << 0;
+    const SP_STATUS_BROKE: u32 = 1 << 1;
+    const SP_STATUS_DMA_BUSY: u32 = 1 << 2;
+    const SP_STATUS_DMA_FULL: u32 = 1 << 3;
+    const SP_STATUS_IO_FULL: u32 = 1 << 4;
+    const SP_STATUS_SSTEP: u32 = 1 << 5;
+    const SP_STATUS_INTR_BREAK: u32 = 1 << 6;
+    const SP_STATUS_SIG_SHIFT: u32 = 7;
+
+    // SP status register bits (write side).
+    const SP_WSTATUS_SET_HALT: u32 = 1 << 1;
+    const SP_WSTATUS_CLEAR_BROKE: u32 = 1 << 2;
+    const SP_WSTATUS_CLEAR_INTR: u32 = 1 << 3;
+    const SP_WSTATUS_CLEAR_SSTEP: u32 = 1 << 5;
+    const SP_WSTATUS_CLEAR_INTR_BREAK: u32 = 1 << 7;
+    const SP_WSTATUS_CLEAR_SIGNALS: u32 = 0x00AA_AA00; // Clear bits for signals 0-7
+
+    /// Snapshot of the SP status register.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct RspStatus(pub u32);
+
+    impl RspStatus {
+        /// Raw register value.
+        pub const fn bits(self) -> u32 {
+            return self.0;
+        }
 
-    // Timer flags
-    #[macro_export] macro_rules! TF_ONE_SHOT {() => (0)}   // Timer should fire only once.
-    #[macro_export] macro_rules! TF_CONTINUOUS {() => (1)} // Timer should fire at a regular interval
+        /// The RSP is halted and not executing.
+        pub const fn is_halted(self) -> bool {
+            return self.0 & SP_STATUS_HALT != 0;
+        }
 
-    /// Calculate timer ticks based on microseconds.
-    pub fn TIMER_TICKS(us: c_longlong) -> c_int {
-        return crate::TIMER_TICKS!(us);
-    }
+        /// The RSP stopped on a break instruction.
+        pub const fn is_broke(self) -> bool {
+            return self.0 & SP_STATUS_BROKE != 0;
+        }
 
-    /// Calculate microseconds based on timer ticks.
-    pub fn TIMER_MICROS(tk: c_longlong) -> c_int {
-        return crate::TIMER_MICROS!(tk);
-    }
+        /// A DMA to or from IMEM/DMEM is in progress.
+        pub const fn dma_busy(self) -> bool {
+            return self.0 & SP_STATUS_DMA_BUSY != 0;
+        }
 
-    /// Calculate timer ticks based on microseconds.
-    pub fn TIMER_TICKS_LL(us: c_longlong) -> c_longlong {
-        return crate::TIMER_TICKS_LL!(us);
-    }
+        /// A second DMA is queued behind the one in progress.
+        pub const fn dma_full(self) -> bool {
+            return self.0 & SP_STATUS_DMA_FULL != 0;
+        }
 
-    /// Calculate microseconds based on timer ticks.
-    pub fn TIMER_MICROS_LL(tk: c_longlong) -> c_longlong {
-        return crate::TIMER_MICROS_LL!(tk);
+        /// The CPU is accessing IMEM/DMEM directly.
+        pub const fn io_full(self) -> bool {
+            return self.0 & SP_STATUS_IO_FULL != 0;
+        }
+
+        /// Single-step mode is enabled.
+        pub const fn single_step(self) -> bool {
+            return self.0 & SP_STATUS_SSTEP != 0;
+        }
+
+        /// A break instruction raises the SP interrupt.
+        pub const fn interrupt_on_break(self) -> bool {
+            return self.0 & SP_STATUS_INTR_BREAK != 0;
+        }
+
+        /// State of the eight general purpose signal bits, signal 0 in bit 0.
+        pub const fn signals(self) -> u8 {
+            return (self.0 >> SP_STATUS_SIG_SHIFT) as u8;
+        }
     }
 
-    /// Initialize the timer subsystem.
+    /// Read a value of type T from DMEM at a byte offset.
     ///
-    /// This function will reset the COP0 ticks counter to 0. Even if you later
-    /// access the hardware counter directly (via TICKS_READ()), it should not
-    /// be a problem if you call init() early in the application main.
+    /// DMEM is read directly over the CPU bus one 32-bit word at a time, so the
+    /// offset needn't be aligned. The RSP should be halted (e.g. after
+    /// run_and_wait()) so the value isn't read mid-update. Panics if the value
+    /// would extend past the end of DMEM.
     ///
-    /// Do not modify the COP0 ticks counter after calling this function. Doing
-    /// so will impede functionality of the timer module.
-    pub fn init() {
-        unsafe { bindings::timer_init(); }
+    /// Safety: every bit pattern must be a valid T, as the bytes are taken as-is.
+    /// Integers, floats and arrays or repr(C) structs of them are fine; bool,
+    /// char, enums and references are not.
+    pub unsafe fn read_data_struct<T: Copy>(offset: usize) -> T {
+        let size = core::mem::size_of::<T>();
+        let end = offset.checked_add(size).filter(|end| *end <= MEM_SIZE);
+        let end = match end {
+            Some(end) => end,
+            None => panic!("RSP::read_data_struct: {} bytes at offset {} exceeds DMEM size {}", size, offset, MEM_SIZE)
+        };
+
+        let mut value = core::mem::MaybeUninit::<T>::uninit();
+        let dst = value.as_mut_ptr() as *mut u8;
+
+        for word_offset in ((offset & !3)..end).step_by(4) {
+            let word = core::ptr::read_volatile(crate::SP_DMEM!().add(word_offset / 4)).to_ne_bytes();
+
+            for (i, byte) in word.iter().enumerate() {
+                let pos = word_offset + i;
+                if pos >= offset && pos < end {
+                    dst.add(pos - offset).write(*byte);
+                }
+            }
+        }
+
+        return value.assume_init();
     }
 
-    /// Create a new timer and add to list.
-    pub fn new_timer(ticks: c_int, flags: c_int, callback: extern "C" fn(overflow: c_int)) -> Option<&'static TimerLink> {
-        unsafe { return bindings::new_timer(ticks, flags, Some(callback)).as_ref(); }
+    /// A microcode blob: the text segment for IMEM and its matching data segment for DMEM.
+    #[derive(Clone, Copy, Debug)]
+    pub struct Microcode<'a> {
+        text: &'a [u8],
+        data: &'a [u8],
     }
 
-    /// Start a timer not currently in the list.
-    pub fn start_timer(timer: &mut TimerLink, ticks: c_int, flags: c_int, callback: extern "C" fn(overflow: c_int)) {
-        unsafe { bindings::start_timer(timer, ticks, flags, Some(callback)); }
+    impl<'a> Microcode<'a> {
+        /// Bundle a text and data segment.
+        ///
+        /// Panics if either segment is larger than 4KB. The data segment may be
+        /// empty for microcode that doesn't need initialized DMEM.
+        pub fn from_slices(text: &'a [u8], data: &'a [u8]) -> Microcode<'a> {
+            assert!(text.len() <= MEM_SIZE, "RSP::Microcode: text segment is {} bytes, IMEM holds {}", text.len(), MEM_SIZE);
+            assert!(data.len() <= MEM_SIZE, "RSP::Microcode: data segment is {} bytes, DMEM holds {}", data.len(), MEM_SIZE);
+
+            return Microcode { text, data };
+        }
+
+        /// The IMEM segment.
+        pub fn text(&self) -> &'a [u8] {
+            return self.text;
+        }
+
+        /// The DMEM segment.
+        pub fn data(&self) -> &'a [u8] {
+            return self.data;
+        }
+
+        /// Load the text segment into IMEM, then the data segment into DMEM.
+        ///
+        /// Nothing is loaded if either segment fails the transfer checks.
+        pub fn load(&self) -> Result<(), RspError> {
+            check_dma(self.text.as_ptr(), self.text.len())?;
+            if !self.data.is_empty() {
+                check_dma(self.data.as_ptr(), self.data.len())?;
+            }
+
+            load_ucode(self.text)?;
+
+            if !self.data.is_empty() {
+                load_data_from(self.data)?;
+            }
+
+            return Ok(());
+        }
+
+        /// Load both segments and start the RSP.
+        ///
+        /// Returns as soon as the RSP has been started. To block until it halts,
+        /// call load() followed by run_and_wait() instead.
+        pub fn run(&self) -> Result<(), RspError> {
+            self.load()?;
+            run_microcode();
+
+            return Ok(());
+        }
     }
 
-    /// Stop a timer and remove it from the list.
-    ///
-    /// Note: This function does not free a timer structure. Use delete_timer() to do this.
-    pub fn stop_timer(timer: &mut TimerLink) {
-        unsafe { bindings::stop_timer(timer); }
+    /// Read the SP status register.
+    pub fn status() -> RspStatus {
+        unsafe { return RspStatus(core::ptr::read_volatile(crate::SP_STATUS!())); }
     }
 
-    /// Remove a timer from the list and delete it.
-    pub fn delete_timer(timer: &mut TimerLink) {
-        unsafe { bindings::delete_timer(timer); }
+    fn write_status(bits: u32) {
+        unsafe { core::ptr::write_volatile(crate::SP_STATUS!(), bits); }
     }
 
-    /// Free and close the timer subsystem.
+    /// Stop the RSP where it is.
     ///
-    /// This function will ensure all recurring timers are deleted from the list before closing.
-    /// One-shot timers that have expired will need to be manually deleted with delete_timer().
-    pub fn timer_close() {
-        unsafe { bindings::timer_close(); }
+    /// Execution can't be resumed meaningfully afterwards; load and run new
+    /// microcode, ideally after reset().
+    pub fn halt() {
+        write_status(SP_WSTATUS_SET_HALT);
     }
 
-    /// Return total ticks since timer was initialized, as a 64-bit counter.
-    pub fn timer_ticks() -> c_long {
-        unsafe { return bindings::timer_ticks(); }
+    /// Halt the RSP and return it to a clean state for the next load and run.
+    ///
+    /// This clears the broke flag, single-step mode, interrupt-on-break and all
+    /// signal bits, and rewinds the program counter to the start of IMEM. It also
+    /// acknowledges any pending SP interrupt, so a registered SP handler will not
+    /// be called for a break that happened before the reset. run_microcode()
+    /// re-arms interrupt-on-break when the RSP is started again.
+    pub fn reset() {
+        write_status(
+            SP_WSTATUS_SET_HALT | SP_WSTATUS_CLEAR_BROKE | SP_WSTATUS_CLEAR_INTR |
+            SP_WSTATUS_CLEAR_SSTEP | SP_WSTATUS_CLEAR_INTR_BREAK | SP_WSTATUS_CLEAR_SIGNALS
+        );
+
+        unsafe { core::ptr::write_volatile(crate::SP_PC!(), 0); }
     }
-}
 
-/// Handle hardware-generated exceptions.
-///
-/// The exception handler traps exceptions generated by hardware. This could be an invalid
+    static RUN_DONE: AtomicBool = AtomicBool::new(false);
+
+    extern "C" fn run_done() {
+        RUN_DONE.store(true, Ordering::Release);
+    }
+
+    /// Size in bytes of both IMEM and DMEM.
+    pub const MEM_SIZE: usize = 4096;
+
+    /// SP DMA transfers move whole 8-byte words from 8-byte aligned RDRAM.
+    const DMA_ALIGN: usize = 8;
+
+    // The DMA rounds the length up to whole words, so a ragged length would copy
+    // past the end of the slice. Pure arithmetic on the address, so it doesn't
+    // depend on the target.
+    fn check_transfer(address: usize, len: usize) -> Result<(), RspError> {
+        if len == 0 || len > MEM_SIZE {
+            return Err(RspError::InvalidSize);
+        }
+
+        if address % DMA_ALIGN != 0 || len % DMA_ALIGN != 0 {
+            return Err(RspError::Unaligned);
+        }
+
+        return Ok(());
+    }
+
+    fn check_dma(ptr: *const u8, len: usize) -> Result<(), RspError> {
+        return check_transfer(ptr as usize, len);
+    }
+
+    ///
+    pub fn init() {
+        unsafe { bindings::rsp_init(); }
+    }
+
+    /// Load microcode into IMEM, taking the size from the slice.
+    ///
+    /// The slice must be 1 to 4096 bytes, 8-byte aligned and a whole number of
+    /// 8-byte words.
+    pub fn load_ucode(code: &[u8]) -> Result<(), RspError> {
+        check_dma(code.as_ptr(), code.len())?;
+        unsafe { bindings::load_ucode(code.as_ptr() as *mut c_void, code.len() as c_ulong); }
+
+        return Ok(());
+    }
+
+    /// Load data into DMEM, taking the size from the slice.
+    ///
+    /// The slice must be 1 to 4096 bytes, 8-byte aligned and a whole number of
+    /// 8-byte words.
+    pub fn load_data_from(data: &[u8]) -> Result<(), RspError> {
+        check_dma(data.as_ptr(), data.len())?;
+        unsafe { bindings::load_data(data.as_ptr() as *mut c_void, data.len() as c_ulong); }
+
+        return Ok(());
+    }
+
+    /// Read the start of IMEM back into a buffer, filling all of it.
+    ///
+    /// The buffer must be 1 to 4096 bytes, 8-byte aligned and a whole number of
+    /// 8-byte words.
+    pub fn read_ucode_into(buf: &mut [u8]) -> Result<(), RspError> {
+        check_dma(buf.as_ptr(), buf.len())?;
+        unsafe { bindings::read_ucode(buf.as_mut_ptr() as *mut c_void, buf.len() as c_ulong); }
+
+        return Ok(());
+    }
+
+    /// Read the start of DMEM back into a buffer, filling all of it.
+    ///
+    /// The buffer must be 1 to 4096 bytes, 8-byte aligned and a whole number of
+    /// 8-byte words.
+    pub fn read_data_into(buf: &mut [u8]) -> Result<(), RspError> {
+        check_dma(buf.as_ptr(), buf.len())?;
+        unsafe { bindings::read_data(buf.as_mut_ptr() as *mut c_void, buf.len() as c_ulong); }
+
+        return Ok(());
+    }
+
+    /// Make a sprite coherent in RDRAM and return an uncached pointer to it.
+    ///
+    /// This doesn't copy anything: it writes back the data cache lines covering
+    /// the sprite header and pixels (width * height * bitdepth bytes), so DMA
+    /// engines such as the RDP see what the CPU last wrote, and returns the same
+    /// sprite through the uncached segment. Cast it back to a Sprite for
+    /// RDP::load_texture(). Writes through the cached address after staging must
+    /// be staged again.
+    pub fn stage_texture(sprite: &Sprite) -> *mut () {
+        let pixels = sprite.width as usize * sprite.height as usize * sprite.bitdepth as usize;
+        let length = core::mem::size_of::<Sprite>() + pixels;
+        let addr = sprite as *const Sprite as *mut c_void;
+
+        unsafe { bindings::data_cache_hit_writeback(Volatile::new(addr), length as c_ulong); }
+
+        return N64System::get_uncached_address(addr as u32) as *mut ();
+    }
+
+    ///
+    pub fn load_microcode(start: &mut c_void, size: c_ulong) {
+        unsafe { bindings::load_ucode(start, size); }
+    }
+
+    ///
+    pub fn load_data(start: &mut c_void, size: c_ulong) {
+        unsafe { bindings::load_data(start, size); }
+    }
+
+    ///
+    pub fn read_microcode(start: &mut c_void, size: c_ulong) {
+        unsafe { bindings::read_ucode(start, size); }
+    }
+
+    ///
+    pub fn read_data(start: &mut c_void, size: c_ulong) {
+        unsafe { bindings::read_data(start, size); }
+    }
+
+    ///
+    pub fn run_microcode() {
+        unsafe { bindings::run_ucode(); }
+    }
+
+    /// Run the loaded microcode and block until it halts.
+    ///
+    /// Completion is signalled by the SP interrupt, which run_microcode() arms to
+    /// fire when the microcode executes a break instruction. That path requires
+    /// Interrupt::init() to have been called and interrupts to be enabled; if they
+    /// aren't, the SP status register is polled instead.
+    pub fn run_and_wait() -> Result<(), RspError> {
+        return run_until(None);
+    }
+
+    /// Like run_and_wait(), but give up after timeout_ms milliseconds.
+    ///
+    /// The timeout must stay well below the ~45 second range of the tick counter.
+    pub fn run_and_wait_timeout(timeout_ms: u32) -> Result<(), RspError> {
+        let deadline = N64System::get_ticks_read().wrapping_add(N64System::get_ticks_from_ms(timeout_ms));
+        return run_until(Some(deadline));
+    }
+
+    fn run_until(deadline: Option<u32>) -> Result<(), RspError> {
+        let use_interrupt = match Interrupt::get_interrupts_state() {
+            InterruptState::INTERRUPTS_ENABLED => true,
+            _ => false
+        };
+
+        if use_interrupt {
+            RUN_DONE.store(false, Ordering::Relaxed);
+            Interrupt::register_SP_handler(run_done);
+            Interrupt::set_SP_interrupt(true);
+        }
+
+        run_microcode();
+
+        let mut result = Ok(());
+        loop {
+            let done = match use_interrupt {
+                true => RUN_DONE.load(Ordering::Acquire),
+                false => status().is_halted()
+            };
+
+            if done {
+                break;
+            }
+
+            if let Some(deadline) = deadline {
+                if !N64System::get_ticks_before(N64System::get_ticks_read(), deadline) {
+                    result = Err(RspError::Timeout);
+                    break;
+                }
+            }
+        }
+
+        if use_interrupt {
+            Interrupt::unregister_SP_handler(run_done);
+        }
+
+        return result;
+    }
+
+    /// Dispatch a job: upload ucode to IMEM and data to DMEM, run until the RSP
+    /// halts, then read DMEM back into data.
+    ///
+    /// Both slices must be 1 to 4096 bytes, 8-byte aligned (e.g. a #[repr(align(8))]
+    /// wrapper) and a whole number of 8-byte words; nothing is uploaded if either
+    /// isn't. The RSP is reset first, and the data cache is written back before
+    /// the upload and invalidated before the read back, so data needn't be in
+    /// uncached memory. Waiting is as for run_and_wait(); data is left untouched
+    /// if that fails.
+    pub fn run(ucode: &[u8], data: &mut [u8]) -> Result<(), RspError> {
+        check_dma(ucode.as_ptr(), ucode.len())?;
+        check_dma(data.as_ptr(), data.len())?;
+
+        let length = data.len() as u32;
+
+        reset();
+
+        unsafe { bindings::data_cache_hit_writeback(Volatile::new(ucode.as_ptr() as *mut c_void), ucode.len() as c_ulong); }
+        N64System::data_cache_hit_writeback(unsafe { &mut *(data.as_mut_ptr() as *mut c_void) }, length);
+
+        load_ucode(ucode)?;
+        load_data_from(data)?;
+        run_and_wait()?;
+
+        N64System::data_cache_hit_invalidate(unsafe { &mut *(data.as_mut_ptr() as *mut c_void) }, length);
+        return read_data_into(data);
+    }
+
+    #[cfg(test)]
+    mod tests {
+        use super::*;
+
+        #[test]
+        fn check_transfer_accepts_aligned_fit() {
+            assert!(check_transfer(0x8010_0000, 8).is_ok());
+            assert!(check_transfer(0x8010_0008, MEM_SIZE).is_ok());
+        }
+
+        #[test]
+        fn check_transfer_rejects_unaligned_address() {
+            assert!(matches!(check_transfer(0x8010_0004, 16), Err(RspError::Unaligned)));
+        }
+
+        #[test]
+        fn check_transfer_rejects_unaligned_length() {
+            assert!(matches!(check_transfer(0x8010_0000, 12), Err(RspError::Unaligned)));
+        }
+
+        #[test]
+        fn check_transfer_rejects_zero_length() {
+            assert!(matches!(check_transfer(0x8010_0000, 0), Err(RspError::InvalidSize)));
+        }
+
+        #[test]
+        fn check_transfer_rejects_larger_than_dmem() {
+            assert!(matches!(check_transfer(0x8010_0000, MEM_SIZE + DMA_ALIGN), Err(RspError::InvalidSize)));
+        }
+    }
+}
+
+/// Interface to the timer module in the MIPS r4300 processor.
+///
+/// The timer subsystem allows code to receive a callback after a specified number
+/// of ticks or microseconds. It interfaces with the MIPS coprocessor 0 to handle
+/// the timer interrupt and provide useful timing services.
+///
+/// Before attempting to use the timer subsystem, code should call timer_init.
+/// After the timer subsystem has been initialized, a new one-shot or continuous
+/// timer can be created with new_timer(). To remove an expired one-shot timer or
+/// a recurring timer, use delete_timer(). To temporarily stop a timer, use stop_timer().
+/// To restart a stopped timer or an expired one-shot timer, use start_timer().
+/// Once code no longer needs the timer subsystem, a call to timer_close() will free
+/// all continuous timers and shut down the timer subsystem. Note that timers removed
+/// with stop_timer() or expired one-short timers will not be removed automatically
+/// and are the responsibility of the calling code to be freed, regardless of a call
+/// to timer_close().
+///
+/// Because the MIPS internal counter wraps around after ~90 seconds (see TICKS_READ),
+/// it's not possible to schedule a timer more than 90 seconds in the future.
+pub mod Timer {
+    use core::sync::atomic::{AtomicBool, Ordering};
+    use cty::*;
+
+    use crate::{Interrupt, N64System, bindings};
+
+    #[repr(C)]
+    pub struct TimerLink {
+        pub left: c_uint,
+        pub set: c_uint,
+        pub ovfl: c_int,
+        pub flags: c_int,
+        pub callback: extern "C" fn(ovfl: c_int),
+    }
+
+    // Timer flags
+    #[macro_export] macro_rules! TF_ONE_SHOT {() => (0)}   // Timer should fire only once.
+    #[macro_export] macro_rules! TF_CONTINUOUS {() => (1)} // Timer should fire at a regular interval
+
+    /// Type-checked timer flags for the higher-level timer constructors.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct Flags(c_int);
+
+    impl Flags {
+        /// Fire once (TF_ONE_SHOT).
+        pub const fn one_shot() -> Flags {
+            return Flags(crate::TF_ONE_SHOT!());
+        }
+
+        /// Fire at a regular interval (TF_CONTINUOUS).
+        pub const fn continuous() -> Flags {
+            return Flags(crate::TF_CONTINUOUS!());
+        }
+
+        /// The raw value passed to libdragon.
+        pub const fn bits(self) -> c_int {
+            return self.0;
+        }
+    }
+
+    /// Calculate timer ticks based on microseconds.
+    pub fn TIMER_TICKS(us: c_longlong) -> c_int {
+        return crate::TIMER_TICKS!(us);
+    }
+
+    /// Calculate microseconds based on timer ticks.
+    pub fn TIMER_MICROS(tk: c_longlong) -> c_int {
+        return crate::TIMER_MICROS!(tk);
+    }
+
+    /// Calculate timer ticks based on microseconds.
+    pub fn TIMER_TICKS_LL(us: c_longlong) -> c_longlong {
+        return crate::TIMER_TICKS_LL!(us);
+    }
+
+    /// Calculate microseconds based on timer ticks.
+    pub fn TIMER_MICROS_LL(tk: c_longlong) -> c_longlong {
+        return crate::TIMER_MICROS_LL!(tk);
+    }
+
+    /// Initialize the timer subsystem.
+    ///
+    /// This function will reset the COP0 ticks counter to 0. Even if you later
+    /// access the hardware counter directly (via TICKS_READ()), it should not
+    /// be a problem if you call init() early in the application main.
+    ///
+    /// Do not modify the COP0 ticks counter after calling this function. Doing
+    /// so will impede functionality of the timer module.
+    pub fn init() {
+        unsafe { bindings::timer_init(); }
+        INITIALIZED.store(true, Ordering::Release);
+    }
+
+    static INITIALIZED: AtomicBool = AtomicBool::new(false);
+
+    /// Return whether init() has been called without a later timer_close().
+    pub fn is_initialized() -> bool {
+        return INITIALIZED.load(Ordering::Acquire);
+    }
+
+    /// A timer allocated by libdragon, as returned by new_timer().
+    ///
+    /// libdragon links the node it allocated into its own timer list and keeps
+    /// updating it from the timer interrupt, so the node must always be referred
+    /// to by that address. A copy of the TimerLink would go stale immediately,
+    /// and passing its address to stop_timer() or delete_timer() would make
+    /// libdragon unlink or free memory it never allocated. The handle therefore
+    /// only carries the pointer, and isn't Clone so it can't outlive delete_timer().
+    ///
+    /// The handle owns the timer and deletes it when dropped. timer_close() frees
+    /// every timer still in the list, so a handle dropped after it leaks its
+    /// timer rather than freeing it twice.
+    pub struct TimerHandle(*mut TimerLink);
+
+    impl TimerHandle {
+        /// The libdragon-owned node, for passing back to C code.
+        pub fn as_ptr(&self) -> *mut TimerLink {
+            return self.0;
+        }
+
+        /// Start the timer, which must not currently be in the list. See start_timer().
+        pub fn start(&self, ticks: c_int, flags: c_int, callback: extern "C" fn(overflow: c_int)) {
+            start_timer(self, ticks, flags, callback);
+        }
+
+        /// Stop the timer and remove it from the list, without freeing it. See stop_timer().
+        pub fn stop(&self) {
+            stop_timer(self);
+        }
+
+        /// Ticks left before the timer next fires.
+        ///
+        /// Read volatile, as the timer interrupt updates it.
+        pub fn left(&self) -> c_uint {
+            unsafe { return core::ptr::read_volatile(&(*self.0).left); }
+        }
+
+        /// Number of ticks the timer was last started with.
+        pub fn set(&self) -> c_uint {
+            unsafe { return core::ptr::read_volatile(&(*self.0).set); }
+        }
+
+        /// Flags the timer was last started with.
+        pub fn flags(&self) -> c_int {
+            unsafe { return core::ptr::read_volatile(&(*self.0).flags); }
+        }
+    }
+
+    impl Drop for TimerHandle {
+        fn drop(&mut self) {
+            if is_initialized() {
+                unsafe { bindings::delete_timer(self.0); }
+            }
+        }
+    }
+
+    /// Create a new timer and add to list.
+    ///
+    /// Returns None if the timer subsystem isn't initialized or libdragon
+    /// couldn't allocate the timer.
+    pub fn new_timer(ticks: c_int, flags: c_int, callback: extern "C" fn(overflow: c_int)) -> Option<TimerHandle> {
+        // Creating a timer before init() hangs or corrupts libdragon's list.
+        if !is_initialized() {
+            return None;
+        }
+
+        let link = unsafe { bindings::new_timer(ticks, flags, Some(callback)) };
+
+        if link.is_null() {
+            return None;
+        }
+
+        return Some(TimerHandle(link));
+    }
+
+    /// Errors returned by the millisecond timer constructors.
+    #[derive(Debug)]
+    pub enum TimerError {
+        NotInitialized,  // Timer::init() hasn't been called
+        IntervalTooLong, // The interval doesn't fit in new_timer()'s tick count
+        Unavailable      // No closure slot was free, or libdragon couldn't allocate the timer
+    }
+
+    /// Maximum number of Timer and ClosureTimer handles alive at once.
+    pub const TRACKED_TIMERS: usize = 16;
+
+    #[derive(Clone, Copy)]
+    enum TrackedState {
+        Stopped,
+        Running { started: i64 },
+        Paused { remaining: c_int },
+    }
+
+    // What pause_all()/resume_all() need to know about each handle's timer.
+    #[derive(Clone, Copy)]
+    struct Tracked {
+        link: *mut TimerLink,
+        interval: c_int,
+        flags: Flags,
+        callback: extern "C" fn(c_int),
+        state: TrackedState,
+    }
+
+    static mut TRACKED: [Option<Tracked>; TRACKED_TIMERS] = [None; TRACKED_TIMERS];
+    static PAUSED: AtomicBool = AtomicBool::new(false);
+
+    fn with_tracked<R>(f: impl FnOnce(&mut [Option<Tracked>; TRACKED_TIMERS]) -> R) -> R {
+        Interrupt::disable_interrupts();
+        let result = unsafe { f(&mut TRACKED) };
+        Interrupt::enable_interrupts();
+
+        return result;
+    }
+
+    // Record a timer that libdragon has just started.
+    fn track(link: *mut TimerLink, interval: c_int, flags: Flags, callback: extern "C" fn(c_int)) -> bool {
+        return with_tracked(|tracked| {
+            match tracked.iter_mut().find(|entry| entry.is_none()) {
+                Some(entry) => {
+                    *entry = Some(Tracked { link, interval, flags, callback, state: TrackedState::Running { started: ticks() } });
+                    true
+                },
+                None => false
+            }
+        });
+    }
+
+    fn retrack(link: *mut TimerLink, update: impl FnOnce(&mut Tracked)) {
+        with_tracked(|tracked| {
+            if let Some(entry) = tracked.iter_mut().flatten().find(|entry| entry.link == link) {
+                update(entry);
+            }
+        });
+    }
+
+    fn untrack(link: *mut TimerLink) {
+        with_tracked(|tracked| {
+            for entry in tracked.iter_mut() {
+                if entry.map_or(false, |entry| entry.link == link) {
+                    *entry = None;
+                }
+            }
+        });
+    }
+
+    /// Suspend every running timer owned by a Timer or ClosureTimer handle.
+    ///
+    /// Meant for pausing a game so timers don't advance while paused. One-shot
+    /// timers remember how many ticks they had left and fire that many ticks
+    /// after resume_all(), so they keep tick accuracy but can be off by up to the
+    /// time the pause itself took to run. Continuous timers resume with a fresh
+    /// full interval; the partial interval in progress when paused is lost.
+    /// One-shots that already fired stay stopped. Timers created through the raw
+    /// new_timer() are not tracked and keep running. Calling this while already
+    /// paused does nothing.
+    pub fn pause_all() {
+        with_tracked(|tracked| {
+            if PAUSED.swap(true, Ordering::AcqRel) {
+                return;
+            }
+
+            let now = ticks();
+            for entry in tracked.iter_mut().flatten() {
+                if let TrackedState::Running { started } = entry.state {
+                    unsafe { bindings::stop_timer(entry.link); }
+
+                    let remaining = match entry.flags == Flags::continuous() {
+                        true => entry.interval as i64,
+                        false => entry.interval as i64 - (now - started)
+                    };
+
+                    entry.state = match remaining > 0 {
+                        true => TrackedState::Paused { remaining: remaining as c_int },
+                        false => TrackedState::Stopped
+                    };
+                }
+            }
+        });
+    }
+
+    /// Restart every timer suspended by pause_all().
+    ///
+    /// Handles started or stopped while paused are left as they are.
+    pub fn resume_all() {
+        with_tracked(|tracked| {
+            if !PAUSED.swap(false, Ordering::AcqRel) {
+                return;
+            }
+
+            let now = ticks();
+            for entry in tracked.iter_mut().flatten() {
+                if let TrackedState::Paused { remaining } = entry.state {
+                    unsafe { bindings::start_timer(entry.link, remaining, entry.flags.bits(), Some(entry.callback)); }
+
+                    // Backdate the start so a second pause sees the right remaining ticks.
+                    entry.state = TrackedState::Running { started: now - (entry.interval - remaining) as i64 };
+                }
+            }
+        });
+    }
+
+    /// A timer owned by Rust code, deleted when the handle is dropped.
+    ///
+    /// This frees the timer whether it is running, stopped or an expired
+    /// one-shot, so callers no longer need to pair new_timer() with delete_timer().
+    /// Its timer is suspended by pause_all().
+    pub struct Timer {
+        handle: TimerHandle,
+        ticks: c_int,
+        flags: Flags,
+        callback: extern "C" fn(overflow: c_int),
+    }
+
+    impl Timer {
+        /// Create a new timer and add it to the list.
+        ///
+        /// Returns None if libdragon couldn't allocate the timer or TRACKED_TIMERS
+        /// handles already exist.
+        pub fn new(ticks: c_int, flags: Flags, callback: extern "C" fn(overflow: c_int)) -> Option<Timer> {
+            let handle = new_timer(ticks, flags.bits(), callback)?;
+
+            if !track(handle.as_ptr(), ticks, flags, callback) {
+                delete_timer(handle);
+                return None;
+            }
+
+            return Some(Timer { handle, ticks, flags, callback });
+        }
+
+        /// Start the timer with new parameters, replacing the current ones.
+        ///
+        /// A running timer is stopped first, so it's never in the list twice.
+        pub fn start(&mut self, ticks: c_int, flags: Flags, callback: extern "C" fn(overflow: c_int)) {
+            self.ticks = ticks;
+            self.flags = flags;
+            self.callback = callback;
+            self.restart();
+        }
+
+        /// Stop the timer and remove it from the list, without freeing it.
+        pub fn stop(&mut self) {
+            stop_timer(&self.handle);
+            retrack(self.handle.as_ptr(), |entry| entry.state = TrackedState::Stopped);
+        }
+
+        /// Start the timer again from the full interval, with its current parameters.
+        ///
+        /// Use this to re-arm an expired one-shot or reset a continuous timer.
+        pub fn restart(&mut self) {
+            stop_timer(&self.handle);
+            start_timer(&self.handle, self.ticks, self.flags.bits(), self.callback);
+
+            let (interval, flags, callback) = (self.ticks, self.flags, self.callback);
+            retrack(self.handle.as_ptr(), |entry| {
+                *entry = Tracked { link: entry.link, interval, flags, callback, state: TrackedState::Running { started: ticks() } };
+            });
+        }
+    }
+
+    impl Drop for Timer {
+        fn drop(&mut self) {
+            // The handle deletes the timer once it's dropped after this.
+            untrack(self.handle.as_ptr());
+        }
+    }
+
+    /// Maximum number of closure timers alive at once.
+    pub const CLOSURE_TIMERS: usize = 8;
+
+    /// Maximum size in bytes of a closure passed to new_timer_fn().
+    pub const CLOSURE_CAPACITY: usize = 32;
+
+    // libdragon's callback gets no context, so each slot has its own trampoline
+    // and the closure lives inline in the slot (there is no allocator to box it).
+    #[derive(Clone, Copy)]
+    struct ClosureSlot {
+        link: *mut TimerLink,
+        storage: [u64; CLOSURE_CAPACITY / 8],
+        call: Option<unsafe fn(*mut u8, c_int)>,
+        drop: Option<unsafe fn(*mut u8)>,
+        detached: bool, // Owned by delay() rather than a ClosureTimer handle
+    }
+
+    const EMPTY_SLOT: ClosureSlot = ClosureSlot {
+        link: core::ptr::null_mut(),
+        storage: [0; CLOSURE_CAPACITY / 8],
+        call: None,
+        drop: None,
+        detached: false,
+    };
+
+    // Only touched with interrupts disabled, or from the timer interrupt itself.
+    static mut CLOSURES: [ClosureSlot; CLOSURE_TIMERS] = [EMPTY_SLOT; CLOSURE_TIMERS];
+
+    const TRAMPOLINES: [extern "C" fn(c_int); CLOSURE_TIMERS] = [
+        trampoline::<0>, trampoline::<1>, trampoline::<2>, trampoline::<3>,
+        trampoline::<4>, trampoline::<5>, trampoline::<6>, trampoline::<7>
+    ];
+
+    extern "C" fn trampoline<const SLOT: usize>(ovfl: c_int) {
+        unsafe {
+            let slot = &mut CLOSURES[SLOT];
+            if let Some(call) = slot.call {
+                call(slot.storage.as_mut_ptr() as *mut u8, ovfl);
+
+                // A delay() closure has run its course; the timer itself can't be
+                // deleted from inside libdragon's callback, so reclaim_finished() does it.
+                if slot.detached {
+                    if let Some(drop) = slot.drop {
+                        drop(slot.storage.as_mut_ptr() as *mut u8);
+                    }
+
+                    slot.call = None;
+                    slot.drop = None;
+                }
+            }
+        }
+    }
+
+    // Free the timers of delay() closures that have already run. Interrupts must
+    // be disabled. After timer_close() the links are leaked rather than freed twice.
+    unsafe fn reclaim_finished() {
+        for slot in CLOSURES.iter_mut() {
+            if slot.detached && slot.call.is_none() {
+                untrack(slot.link);
+                if is_initialized() {
+                    bindings::delete_timer(slot.link);
+                }
+                *slot = EMPTY_SLOT;
+            }
+        }
+    }
+
+    unsafe fn call_closure<F: FnMut(i32)>(storage: *mut u8, ovfl: c_int) {
+        (*(storage as *mut F))(ovfl);
+    }
+
+    unsafe fn drop_closure<F>(storage: *mut u8) {
+        core::ptr::drop_in_place(storage as *mut F);
+    }
+
+    /// A timer running a closure, created by new_timer_fn().
+    ///
+    /// The closure stays alive for as long as the handle does. Dropping the
+    /// handle deletes the timer and then drops the closure.
+    pub struct ClosureTimer {
+        slot: usize,
+    }
+
+    impl Drop for ClosureTimer {
+        fn drop(&mut self) {
+            Interrupt::disable_interrupts();
+
+            unsafe {
+                let slot = &mut CLOSURES[self.slot];
+                untrack(slot.link);
+
+                // timer_close() may already have freed the link; leak it then.
+                if is_initialized() {
+                    bindings::delete_timer(slot.link);
+                }
+
+                if let Some(drop) = slot.drop {
+                    drop(slot.storage.as_mut_ptr() as *mut u8);
+                }
+
+                *slot = EMPTY_SLOT;
+            }
+
+            Interrupt::enable_interrupts();
+        }
+    }
+
+    /// Convert a millisecond interval to timer ticks, rejecting intervals too long
+    /// for new_timer().
+    ///
+    /// new_timer() takes a signed 32-bit tick count, which caps an interval at
+    /// about 45.8 seconds. That is tighter than the ~90 second counter wraparound
+    /// the module docs mention, and going past it would wrap to a negative count.
+    fn ms_to_ticks(ms: u32) -> Result<c_int, TimerError> {
+        let ticks = TIMER_TICKS_LL(ms as c_longlong * 1000);
+
+        if ticks > c_int::MAX as c_longlong {
+            return Err(TimerError::IntervalTooLong);
+        }
+
+        return Ok(ticks as c_int);
+    }
+
+    /// Call a closure once, ms milliseconds from now.
+    ///
+    /// See new_timer_fn() for the closure's constraints; the returned handle must
+    /// be kept alive until the timer fires. Returns NotInitialized if the timer
+    /// subsystem isn't initialized.
+    pub fn after_ms<F: FnMut(i32) + Send + 'static>(ms: u32, f: F) -> Result<ClosureTimer, TimerError> {
+        if !is_initialized() {
+            return Err(TimerError::NotInitialized);
+        }
+
+        let ticks = ms_to_ticks(ms)?;
+        return new_timer_fn(ticks, Flags::one_shot(), f).ok_or(TimerError::Unavailable);
+    }
+
+    /// Call a closure every ms milliseconds until the returned handle is dropped.
+    ///
+    /// See new_timer_fn() for the closure's constraints. Returns NotInitialized
+    /// if the timer subsystem isn't initialized.
+    pub fn every_ms<F: FnMut(i32) + Send + 'static>(ms: u32, f: F) -> Result<ClosureTimer, TimerError> {
+        if !is_initialized() {
+            return Err(TimerError::NotInitialized);
+        }
+
+        let ticks = ms_to_ticks(ms)?;
+        return new_timer_fn(ticks, Flags::continuous(), f).ok_or(TimerError::Unavailable);
+    }
+
+    /// Create a new timer that calls a closure, and add it to the list.
+    ///
+    /// The closure runs in interrupt context with the overflow count, like the
+    /// callback passed to new_timer(). It is stored inline, so it must fit in
+    /// CLOSURE_CAPACITY bytes with at most 8-byte alignment (this panics
+    /// otherwise), and at most CLOSURE_TIMERS closure timers can exist at once.
+    /// Returns None if the timer subsystem isn't initialized, no slot is free,
+    /// TRACKED_TIMERS handles already exist or libdragon couldn't allocate the
+    /// timer. Like Timer, its timer is suspended by pause_all().
+    ///
+    /// As the module docs note, an expired one-shot timer is not freed by
+    /// libdragon. The handle keeps the closure and timer around until it is
+    /// dropped, so drop it once the timer has fired.
+    pub fn new_timer_fn<F: FnMut(i32) + Send + 'static>(ticks: c_int, flags: Flags, f: F) -> Option<ClosureTimer> {
+        assert!(core::mem::size_of::<F>() <= CLOSURE_CAPACITY, "Timer::new_timer_fn(): closure is {} bytes, at most {} fit", core::mem::size_of::<F>(), CLOSURE_CAPACITY);
+        assert!(core::mem::align_of::<F>() <= core::mem::align_of::<u64>(), "Timer::new_timer_fn(): closure alignment {} is too large", core::mem::align_of::<F>());
+
+        if !is_initialized() {
+            return None;
+        }
+
+        Interrupt::disable_interrupts();
+
+        let timer = unsafe {
+            reclaim_finished();
+
+            match CLOSURES.iter().position(|slot| slot.link.is_null()) {
+                Some(index) => {
+                    let slot = &mut CLOSURES[index];
+                    core::ptr::write(slot.storage.as_mut_ptr() as *mut F, f);
+                    slot.call = Some(call_closure::<F>);
+                    slot.drop = Some(drop_closure::<F>);
+                    slot.link = bindings::new_timer(ticks, flags.bits(), Some(TRAMPOLINES[index]));
+
+                    if !slot.link.is_null() && !track(slot.link, ticks, flags, TRAMPOLINES[index]) {
+                        bindings::delete_timer(slot.link);
+                        slot.link = core::ptr::null_mut();
+                    }
+
+                    match slot.link.is_null() {
+                        true => {
+                            drop_closure::<F>(slot.storage.as_mut_ptr() as *mut u8);
+                            *slot = EMPTY_SLOT;
+                            None
+                        },
+                        false => Some(ClosureTimer { slot: index })
+                    }
+                },
+                None => None
+            }
+        };
+
+        Interrupt::enable_interrupts();
+
+        return timer;
+    }
+
+    /// Run a closure once, ms milliseconds from now, without keeping a handle.
+    ///
+    /// The closure is kept alive until it runs and dropped right after. libdragon
+    /// doesn't free expired one-shot timers and the timer can't be deleted from
+    /// its own callback, so the timer and its closure slot are reclaimed the next
+    /// time a closure timer is created. Until then the slot counts against
+    /// CLOSURE_TIMERS.
+    /// See new_timer_fn() for the closure's size constraints.
+    pub fn delay<F: FnOnce() + Send + 'static>(ms: u32, f: F) -> Result<(), TimerError> {
+        let mut f = Some(f);
+
+        // Keep the timer from firing before the slot is marked detached.
+        Interrupt::disable_interrupts();
+
+        let result = after_ms(ms, move |_| {
+            if let Some(f) = f.take() {
+                f();
+            }
+        });
+
+        let result = result.map(|timer| {
+            unsafe { CLOSURES[timer.slot].detached = true; }
+            core::mem::forget(timer);
+        });
+
+        Interrupt::enable_interrupts();
+
+        return result;
+    }
+
+    // Raw context pointer carried into the timer interrupt. Keeping it valid
+    // (and safe to use from the interrupt) is the caller's side of new_timer_ctx().
+    struct TimerContext(*mut ());
+
+    unsafe impl Send for TimerContext {}
+
+    /// Create a new timer whose callback also receives a context pointer.
+    ///
+    /// For C-style callers that can't pass a closure: ctx is stored alongside the
+    /// timer and handed to cb on every firing, without any allocation. ctx must
+    /// stay valid until the returned handle is dropped. Shares the closure slots
+    /// and caveats of new_timer_fn().
+    pub fn new_timer_ctx(ticks: c_int, flags: Flags, ctx: *mut (), cb: extern "C" fn(ovfl: c_int, ctx: *mut ())) -> Option<ClosureTimer> {
+        let ctx = TimerContext(ctx);
+        return new_timer_fn(ticks, flags, move |ovfl| cb(ovfl, ctx.0));
+    }
+
+    /// Start a timer not currently in the list.
+    pub fn start_timer(timer: &TimerHandle, ticks: c_int, flags: c_int, callback: extern "C" fn(overflow: c_int)) {
+        unsafe { bindings::start_timer(timer.0, ticks, flags, Some(callback)); }
+    }
+
+    /// Stop a timer and remove it from the list.
+    ///
+    /// Note: This function does not free a timer structure. Use delete_timer() to do this.
+    pub fn stop_timer(timer: &TimerHandle) {
+        unsafe { bindings::stop_timer(timer.0); }
+    }
+
+    /// Remove a timer from the list and delete it.
+    ///
+    /// This is the same as dropping the handle.
+    pub fn delete_timer(timer: TimerHandle) {
+        drop(timer);
+    }
+
+    /// Free and close the timer subsystem.
+    ///
+    /// This function will ensure all recurring timers are deleted from the list before closing.
+    /// One-shot timers that have expired will need to be manually deleted with delete_timer(),
+    /// before calling this; handles dropped afterwards no longer delete their timer.
+    pub fn timer_close() {
+        INITIALIZED.store(false, Ordering::Release);
+        unsafe { bindings::timer_close(); }
+    }
+
+    /// Return total ticks since timer was initialized, as a 64-bit counter.
+    pub fn timer_ticks() -> c_longlong {
+        unsafe { return bindings::timer_ticks(); }
+    }
+
+    /// Return total ticks since timer was initialized.
+    ///
+    /// Unlike the COP0 count register this is 64-bit and does not wrap around.
+    pub fn ticks() -> i64 {
+        return timer_ticks();
+    }
+
+    /// Milliseconds elapsed since a value previously returned by ticks().
+    pub fn elapsed_ms_since(start: i64) -> i64 {
+        return TIMER_MICROS_LL(ticks() - start) / 1000;
+    }
+
+    /// A span of time in microseconds, as measured between two Instants.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Micros(pub i64);
+
+    impl Micros {
+        pub const fn as_micros(&self) -> i64 {
+            return self.0;
+        }
+
+        pub const fn as_millis(&self) -> i64 {
+            return self.0 / 1000;
+        }
+    }
+
+    /// A point in time on the 64-bit timer tick counter.
+    ///
+    /// Requires the timer subsystem to be initialized.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord)]
+    pub struct Instant(pub i64);
+
+    impl Instant {
+        pub fn now() -> Instant {
+            return Instant(ticks());
+        }
+
+        /// The raw tick count, as returned by ticks().
+        pub const fn ticks(&self) -> i64 {
+            return self.0;
+        }
+
+        /// Time from earlier to this instant; negative if earlier is actually later.
+        pub fn elapsed_since(&self, earlier: Instant) -> Micros {
+            return Micros(TIMER_MICROS_LL(self.0 - earlier.0));
+        }
+
+        /// Time from this instant to now.
+        pub fn elapsed(&self) -> Micros {
+            return Instant::now().elapsed_since(*self);
+        }
+    }
+
+    /// Measures elapsed time with the COP0 count register.
+    ///
+    /// Differences are taken with TICKS_DISTANCE, so a count wraparound between
+    /// readings is handled, but spans longer than ~45 seconds are not. This
+    /// doesn't need the timer subsystem to be initialized.
+    pub struct Stopwatch {
+        start: u32,
+        lap: u32,
+    }
+
+    fn ticks_to_us(ticks: u32) -> u32 {
+        return (ticks as u64 * 1_000_000 / crate::TICKS_PER_SECOND!() as u64) as u32;
+    }
+
+    impl Stopwatch {
+        /// Start a stopwatch now.
+        pub fn start() -> Stopwatch {
+            let now = N64System::get_ticks_read();
+            return Stopwatch { start: now, lap: now };
+        }
+
+        /// Ticks since the previous lap() (or start()), starting a new lap.
+        pub fn lap(&mut self) -> u32 {
+            let now = N64System::get_ticks_read();
+            let ticks = N64System::get_ticks_distance(self.lap, now) as u32;
+            self.lap = now;
+
+            return ticks;
+        }
+
+        /// Ticks since start().
+        pub fn elapsed_ticks(&self) -> u32 {
+            return N64System::get_ticks_distance(self.start, N64System::get_ticks_read()) as u32;
+        }
+
+        /// Microseconds since start().
+        pub fn elapsed_us(&self) -> u32 {
+            return ticks_to_us(self.elapsed_ticks());
+        }
+
+        /// Milliseconds since start().
+        pub fn elapsed_ms(&self) -> u32 {
+            return self.elapsed_us() / 1000;
+        }
+    }
+
+    /// Run a closure and return its result with the microseconds it took.
+    pub fn measure<R>(f: impl FnOnce() -> R) -> (R, u32) {
+        let stopwatch = Stopwatch::start();
+        let result = f();
+
+        return (result, stopwatch.elapsed_us());
+    }
+
+    /// Paces a loop to a fixed rate using the 64-bit tick counter.
+    ///
+    /// Requires the timer subsystem to be initialized.
+    pub struct FrameLimiter {
+        frame_ticks: i64,
+        next: i64,
+        last: i64,
+    }
+
+    impl FrameLimiter {
+        /// Create a limiter for target_hz frames per second, starting now.
+        pub fn new(target_hz: u32) -> FrameLimiter {
+            assert!(target_hz > 0, "Timer::FrameLimiter::new(): target_hz must be non-zero");
+
+            let frame_ticks = TIMER_TICKS_LL(1_000_000) / target_hz as i64;
+            let now = ticks();
+
+            return FrameLimiter { frame_ticks, next: now + frame_ticks, last: now };
+        }
+
+        /// Spin until the next frame boundary and return the microseconds since the
+        /// previous call (or since new()).
+        ///
+        /// Boundaries are scheduled on a fixed grid so small jitter doesn't drift
+        /// the rate. If a frame overran its budget by a whole frame or more, the
+        /// grid restarts from now instead of running later frames back to back
+        /// to catch up.
+        pub fn wait_for_next_frame(&mut self) -> i64 {
+            let mut now = ticks();
+            while now < self.next {
+                now = ticks();
+            }
+
+            if now - self.next >= self.frame_ticks {
+                self.next = now + self.frame_ticks;
+            } else {
+                self.next += self.frame_ticks;
+            }
+
+            let delta = now - self.last;
+            self.last = now;
+
+            return TIMER_MICROS_LL(delta);
+        }
+    }
+}
+
+/// Handle hardware-generated exceptions.
+///
+/// The exception handler traps exceptions generated by hardware. This could be an invalid
 /// instruction or invalid memory access exception or it could be a reset exception. In both cases,
 /// a handler registered with register_exception_handler() will be passed information regarding
 /// the exception type and relevant registers.
 pub mod Exceptions {
+    use core::sync::atomic::{compiler_fence, Ordering};
     use cty::*;
     use volatile::Volatile;
 
-    use crate::bindings;
+    use crate::{COP0, Display::{self, AntiAlias, BitDepth, DisplayContext, Gamma, Resolution}, GraphicsEngine, bindings};
+
+    #[repr(C)]
+    pub struct Exception {
+        pub _type: c_int,
+        pub code: ExceptionCode,
+        pub info: *const c_char,
+        pub regs: *mut RegisterBlock // volatile reg_block_t* in C; the block lives on the interrupt stack
+    }
+
+    #[repr(C)]
+    pub enum ExceptionType {
+        EXCEPTION_TYPE_UNKNOWN = 0,
+        EXCEPTION_TYPE_RESET,
+        EXCEPTION_TYPE_CRITICAL
+    }
+
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum ExceptionCode {
+        EXCEPTION_CODE_INTERRUPT = 0,
+        EXCEPTION_CODE_TLB_MODIFICATION = 1,
+        EXCEPTION_CODE_TLB_LOAD_I_MISS = 2,
+        EXCEPTION_CODE_TLB_STORE_MISS = 3,
+        EXCEPTION_CODE_LOAD_I_ADDRESS_ERROR = 4,
+        EXCEPTION_CODE_STORE_ADDRESS_ERROR = 5,
+        EXCEPTION_CODE_I_BUS_ERROR = 6,
+        EXCEPTION_CODE_D_BUS_ERROR = 7,
+        EXCEPTION_CODE_SYS_CALL = 8,
+        EXCEPTION_CODE_BREAKPOINT = 9,
+        EXCEPTION_CODE_RESERVED_INSTRUCTION = 10,
+        EXCEPTION_CODE_COPROCESSOR_UNUSABLE = 11,
+        EXCEPTION_CODE_ARITHMETIC_OVERFLOW = 12,
+        EXCEPTION_CODE_TRAP = 13,
+        EXCEPTION_CODE_FLOATING_POINT = 15,
+        EXCEPTION_CODE_WATCH = 23,
+    }
+
+    impl ExceptionCode {
+        /// TLB modification, load miss or store miss.
+        pub fn is_tlb(&self) -> bool {
+            return match self {
+                ExceptionCode::EXCEPTION_CODE_TLB_MODIFICATION |
+                ExceptionCode::EXCEPTION_CODE_TLB_LOAD_I_MISS |
+                ExceptionCode::EXCEPTION_CODE_TLB_STORE_MISS => true,
+                _ => false
+            };
+        }
+
+        /// Unaligned or out-of-segment load, store or instruction fetch.
+        pub fn is_address_error(&self) -> bool {
+            return match self {
+                ExceptionCode::EXCEPTION_CODE_LOAD_I_ADDRESS_ERROR |
+                ExceptionCode::EXCEPTION_CODE_STORE_ADDRESS_ERROR => true,
+                _ => false
+            };
+        }
+
+        /// Bus error on instruction fetch or data access.
+        pub fn is_bus_error(&self) -> bool {
+            return match self {
+                ExceptionCode::EXCEPTION_CODE_I_BUS_ERROR |
+                ExceptionCode::EXCEPTION_CODE_D_BUS_ERROR => true,
+                _ => false
+            };
+        }
+    }
+
+    impl core::fmt::Display for ExceptionCode {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let name = match self {
+                ExceptionCode::EXCEPTION_CODE_INTERRUPT => "Interrupt",
+                ExceptionCode::EXCEPTION_CODE_TLB_MODIFICATION => "TLB modification",
+                ExceptionCode::EXCEPTION_CODE_TLB_LOAD_I_MISS => "TLB load miss",
+                ExceptionCode::EXCEPTION_CODE_TLB_STORE_MISS => "TLB store miss",
+                ExceptionCode::EXCEPTION_CODE_LOAD_I_ADDRESS_ERROR => "Address error on load",
+                ExceptionCode::EXCEPTION_CODE_STORE_ADDRESS_ERROR => "Address error on store",
+                ExceptionCode::EXCEPTION_CODE_I_BUS_ERROR => "Bus error on instruction fetch",
+                ExceptionCode::EXCEPTION_CODE_D_BUS_ERROR => "Bus error on data access",
+                ExceptionCode::EXCEPTION_CODE_SYS_CALL => "Syscall",
+                ExceptionCode::EXCEPTION_CODE_BREAKPOINT => "Breakpoint",
+                ExceptionCode::EXCEPTION_CODE_RESERVED_INSTRUCTION => "Reserved instruction",
+                ExceptionCode::EXCEPTION_CODE_COPROCESSOR_UNUSABLE => "Coprocessor unusable",
+                ExceptionCode::EXCEPTION_CODE_ARITHMETIC_OVERFLOW => "Arithmetic overflow",
+                ExceptionCode::EXCEPTION_CODE_TRAP => "Trap",
+                ExceptionCode::EXCEPTION_CODE_FLOATING_POINT => "Floating point exception",
+                ExceptionCode::EXCEPTION_CODE_WATCH => "Watch"
+            };
+
+            return f.write_str(name);
+        }
+    }
+
+    #[repr(C)]
+    pub struct RegisterBlock {
+        pub gpr: [Volatile<c_ulong>; 32],
+        pub sr: Volatile<c_uint>,
+        pub cr: Volatile<c_uint>,
+        pub epc: Volatile<c_uint>,
+        pub hi: Volatile<c_ulong>,
+        pub lo: Volatile<c_ulong>,
+        pub fc31: Volatile<c_uint>,
+        pub fpr: [Volatile<c_ulong>; 32]
+    }
+
+    // Volatile is repr(transparent), so the saved value sits at the field's address.
+    fn read_saved<T: Copy>(reg: &Volatile<T>) -> T {
+        unsafe { return core::ptr::read_volatile(reg as *const Volatile<T> as *const T); }
+    }
+
+    fn write_saved<T: Copy>(reg: &mut Volatile<T>, value: T) {
+        unsafe { core::ptr::write_volatile(reg as *mut Volatile<T> as *mut T, value); }
+    }
+
+    /// Longest info string info_str() will scan.
+    pub const INFO_MAX_LEN: usize = 256;
+
+    impl Exception {
+        /// Address of the faulting instruction, or of its branch when
+        /// cause().branch_delay() is set.
+        pub fn epc(&self) -> u32 {
+            return self.registers().epc();
+        }
+
+        /// Status register as saved when the exception was taken.
+        pub fn status(&self) -> COP0::Status {
+            return COP0::Status(self.registers().sr());
+        }
+
+        /// Cause register as saved when the exception was taken.
+        pub fn cause(&self) -> COP0::Cause {
+            return COP0::Cause(self.registers().cr());
+        }
+
+        /// Address that failed translation or caused an address error.
+        ///
+        /// BadVAddr isn't part of the saved state, so this reads COP0 directly and
+        /// is only meaningful for TLB and address error exceptions, before
+        /// anything else can fault.
+        pub fn bad_vaddr(&self) -> u32 {
+            return COP0::READ_BADVADDR();
+        }
+
+        /// The description libdragon attached to the exception, if any.
+        ///
+        /// The string is scanned for its terminator over at most INFO_MAX_LEN
+        /// bytes and cut there if none is found. Anything from the first byte
+        /// that isn't valid UTF-8 onwards is dropped.
+        pub fn info_str(&self) -> Option<&str> {
+            if self.info.is_null() {
+                return None;
+            }
+
+            let bytes = unsafe {
+                let start = self.info as *const u8;
+                let mut len = 0;
+                while len < INFO_MAX_LEN && *start.add(len) != 0 {
+                    len += 1;
+                }
+
+                core::slice::from_raw_parts(start, len)
+            };
+
+            return match core::str::from_utf8(bytes) {
+                Ok(info) => Some(info),
+                Err(err) => unsafe { Some(core::str::from_utf8_unchecked(&bytes[..err.valid_up_to()])) }
+            };
+        }
+
+        /// The message passed to breakpoint(), if that is what raised this exception.
+        ///
+        /// Returns None if the registers don't point at valid UTF-8, e.g. when a
+        /// handler has modified a0 or a1.
+        pub fn breakpoint_message(&self) -> Option<&'static str> {
+            if self.code != ExceptionCode::EXCEPTION_CODE_BREAKPOINT {
+                return None;
+            }
+
+            let instruction = unsafe { core::ptr::read_volatile(self.epc() as *const u32) };
+            if instruction != BREAKPOINT_INSTRUCTION {
+                return None;
+            }
+
+            let regs = self.registers();
+            let bytes = unsafe { core::slice::from_raw_parts(regs.a0() as *const u8, regs.a1() as usize) };
+
+            return core::str::from_utf8(bytes).ok();
+        }
+
+        /// The saved register state, for reading.
+        pub fn registers(&self) -> &RegisterBlock {
+            unsafe { return &*self.regs; }
+        }
+
+        /// The saved register state, for changing the values restored on return.
+        pub fn registers_mut(&mut self) -> &mut RegisterBlock {
+            unsafe { return &mut *self.regs; }
+        }
+    }
+
+    macro_rules! named_gprs {
+        ($($name:ident, $set_name:ident = $index:expr, $doc:literal;)*) => {
+            $(
+                #[doc = $doc]
+                pub fn $name(&self) -> c_ulong {
+                    return self.gpr($index);
+                }
+
+                /// Set the value restored to the register of the same name.
+                pub fn $set_name(&mut self, value: c_ulong) {
+                    self.set_gpr($index, value);
+                }
+            )*
+        }
+    }
+
+    impl RegisterBlock {
+        /// Saved Status register.
+        pub fn sr(&self) -> c_uint {
+            return read_saved(&self.sr);
+        }
+
+        /// Saved Cause register.
+        pub fn cr(&self) -> c_uint {
+            return read_saved(&self.cr);
+        }
+
+        /// Saved exception program counter.
+        pub fn epc(&self) -> c_uint {
+            return read_saved(&self.epc);
+        }
+
+        /// Set the address execution resumes at when the handler returns.
+        pub fn set_epc(&mut self, value: c_uint) {
+            write_saved(&mut self.epc, value);
+        }
+
+        /// Saved value of general purpose register $index.
+        pub fn gpr(&self, index: usize) -> c_ulong {
+            return read_saved(&self.gpr[index]);
+        }
+
+        /// Set the value restored to general purpose register $index.
+        pub fn set_gpr(&mut self, index: usize, value: c_ulong) {
+            write_saved(&mut self.gpr[index], value);
+        }
+
+        /// Saved HI multiply/divide register.
+        pub fn hi(&self) -> c_ulong {
+            return read_saved(&self.hi);
+        }
+
+        /// Set the value restored to HI.
+        pub fn set_hi(&mut self, value: c_ulong) {
+            write_saved(&mut self.hi, value);
+        }
+
+        /// Saved LO multiply/divide register.
+        pub fn lo(&self) -> c_ulong {
+            return read_saved(&self.lo);
+        }
+
+        /// Set the value restored to LO.
+        pub fn set_lo(&mut self, value: c_ulong) {
+            write_saved(&mut self.lo, value);
+        }
+
+        named_gprs! {
+            at, set_at = 1, "Saved $1 (at), assembler temporary register.";
+            v0, set_v0 = 2, "Saved $2 (v0), return value register.";
+            v1, set_v1 = 3, "Saved $3 (v1), return value register.";
+            a0, set_a0 = 4, "Saved $4 (a0), argument register.";
+            a1, set_a1 = 5, "Saved $5 (a1), argument register.";
+            a2, set_a2 = 6, "Saved $6 (a2), argument register.";
+            a3, set_a3 = 7, "Saved $7 (a3), argument register.";
+            t0, set_t0 = 8, "Saved $8 (t0), temporary register.";
+            t1, set_t1 = 9, "Saved $9 (t1), temporary register.";
+            t2, set_t2 = 10, "Saved $10 (t2), temporary register.";
+            t3, set_t3 = 11, "Saved $11 (t3), temporary register.";
+            t4, set_t4 = 12, "Saved $12 (t4), temporary register.";
+            t5, set_t5 = 13, "Saved $13 (t5), temporary register.";
+            t6, set_t6 = 14, "Saved $14 (t6), temporary register.";
+            t7, set_t7 = 15, "Saved $15 (t7), temporary register.";
+            s0, set_s0 = 16, "Saved $16 (s0), saved register.";
+            s1, set_s1 = 17, "Saved $17 (s1), saved register.";
+            s2, set_s2 = 18, "Saved $18 (s2), saved register.";
+            s3, set_s3 = 19, "Saved $19 (s3), saved register.";
+            s4, set_s4 = 20, "Saved $20 (s4), saved register.";
+            s5, set_s5 = 21, "Saved $21 (s5), saved register.";
+            s6, set_s6 = 22, "Saved $22 (s6), saved register.";
+            s7, set_s7 = 23, "Saved $23 (s7), saved register.";
+            t8, set_t8 = 24, "Saved $24 (t8), temporary register.";
+            t9, set_t9 = 25, "Saved $25 (t9), temporary register.";
+            gp, set_gp = 28, "Saved $28 (gp), global pointer register.";
+            sp, set_sp = 29, "Saved $29 (sp), stack pointer register.";
+            fp, set_fp = 30, "Saved $30 (fp), frame pointer register.";
+            ra, set_ra = 31, "Saved $31 (ra), return address register.";
+        }
+    }
+
+    impl core::fmt::Debug for RegisterBlock {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            let mut dbg = f.debug_struct("RegisterBlock");
+
+            for (index, name) in GPR_NAMES.iter().enumerate() {
+                dbg.field(name, &format_args!("{:#010X}", self.gpr(index)));
+            }
+
+            return dbg
+                .field("sr", &format_args!("{:#010X}", self.sr()))
+                .field("cr", &format_args!("{:#010X}", self.cr()))
+                .field("epc", &format_args!("{:#010X}", self.epc()))
+                .field("hi", &format_args!("{:#010X}", self.hi()))
+                .field("lo", &format_args!("{:#010X}", self.lo()))
+                .finish();
+        }
+    }
+
+    /// Every general purpose register by its conventional name, four per line, then HI and LO.
+    impl core::fmt::Display for RegisterBlock {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            for (index, name) in GPR_NAMES.iter().enumerate() {
+                write!(f, "{}:{:08X}", name, self.gpr(index))?;
+
+                match index % 4 {
+                    3 => writeln!(f)?,
+                    _ => write!(f, " ")?
+                }
+            }
+
+            return writeln!(f, "hi:{:08X} lo:{:08X}", self.hi(), self.lo());
+        }
+    }
+
+    /// Register an exception handler to handle exceptions.
+    ///
+    /// The registered handle is responsible for clearing any bits that may cause a re-trigger
+    /// of the same exception and updating the EPC. An important example is the cause bits (12-17)
+    /// of FCR31 from cop1. To prevent re-triggering the exception they should be cleared by the handler.
+    ///
+    /// To manipulate the registers, update the values in the Exception struct. They will be restored
+    /// to appropriate locations when returning from the handler. Setting them directly will not work
+    /// as expected as they will get overwritten with the values pointed by the struct.
+    ///
+    /// There is only one exception to this, cr (cause register) which is also modified by the int handler
+    /// before the saved values are restored thus it is only possible to update it through COP0::WRITE_CR()
+    /// macro if it is needed. This shouldn't be necessary though as they are already handled by the library.
+    ///
+    /// k0 ($26), k1 ($27) are not saved/restored and will not be available in the handler. Theoretically
+    /// we can exclude s0-s7 ($16-$23), and gp ($28) to gain some performance as they are already saved
+    /// by GCC when necessary. The same is true for sp ($29) and ra ($31) but current interrupt handler
+    /// manipulates them via allocating a new stack and doing a jal. Similarly floating point registers
+    /// f21-f31 are callee-saved. In the future we may consider removing them from the save state for
+    /// interrupts (but not for exceptions)
+    ///
+    /// libdragon holds a single handler, so this replaces whatever was installed
+    /// before, including a closure from set_handler(), which is dropped.
+    pub fn register_exception_handler(callback: extern "C" fn(*mut Exception)) {
+        unsafe {
+            bindings::register_exception_handler(Some(callback));
+            release_closure();
+        }
+    }
+
+    /// Restore libdragon's default exception handler.
+    ///
+    /// Drops any closure installed with set_handler().
+    pub fn clear_handler() {
+        unsafe {
+            bindings::register_exception_handler(Some(default_trampoline));
+            release_closure();
+        }
+    }
+
+    extern "C" fn default_trampoline(exception: *mut Exception) {
+        unsafe { bindings::exception_default_handler(exception); }
+    }
+
+    ///
+    pub fn default_exception_handler(exception: *mut Exception) {
+        unsafe { bindings::exception_default_handler(exception); }
+    }
+
+    const GPR_NAMES: [&str; 32] = [
+        "zr", "at", "v0", "v1", "a0", "a1", "a2", "a3",
+        "t0", "t1", "t2", "t3", "t4", "t5", "t6", "t7",
+        "s0", "s1", "s2", "s3", "s4", "s5", "s6", "s7",
+        "t8", "t9", "k0", "k1", "gp", "sp", "fp", "ra"
+    ];
+
+    /// Write a readable dump of an exception and its saved registers.
+    ///
+    /// Prints the exception code, the saved EPC, Status and Cause registers, the
+    /// BadVAddr register and every general purpose register, four per line. It
+    /// doesn't allocate, so together with a fmt::Write target like the console it
+    /// works from inside an exception handler. BadVAddr isn't part of the saved
+    /// state, so it is read live from COP0; call this before anything that could
+    /// fault again.
+    pub fn dump(ex: &Exception, w: &mut impl core::fmt::Write) -> core::fmt::Result {
+        let regs = ex.registers();
+
+        writeln!(w, "Exception: {} ({})", ex.code, ex.code as u32)?;
+
+        if let Some(info) = ex.info_str() {
+            writeln!(w, "{}", info)?;
+        }
+
+        if let Some(msg) = ex.breakpoint_message() {
+            writeln!(w, "{}", msg)?;
+        }
+
+        writeln!(w, "EPC: {:08X}  SR: {:08X}  CR: {:08X}", ex.epc(), ex.status().bits(), ex.cause().bits())?;
+        writeln!(w, "BadVAddr: {:08X}", ex.bad_vaddr())?;
+
+        return write!(w, "{}", regs);
+    }
+
+    /// Register a closure that runs before libdragon's default exception handler.
+    ///
+    /// This is the safe way to add logging or bookkeeping without taking over
+    /// the whole recovery path: f sees the exception first, then
+    /// default_exception_handler() handles it as if no handler were installed.
+    /// Register changes f makes through the Exception are written to the saved
+    /// state with volatile stores before the default handler runs, so it sees
+    /// them. The constraints of set_handler() apply to f.
+    pub fn set_handler_then_default<F: FnMut(&mut Exception) + Send + 'static>(mut f: F) {
+        set_handler(move |exception: &mut Exception| {
+            f(exception);
+            compiler_fence(Ordering::SeqCst);
+            default_exception_handler(exception);
+        });
+    }
+
+    // Font cell size used by graphics_draw_text().
+    const GLYPH_WIDTH: u32 = 8;
+    const LINE_HEIGHT: u32 = 10;
+    const MAX_COLUMNS: usize = 80;
+
+    // Draws text line by line onto one display buffer, wrapping at the screen edge.
+    struct CrashWriter {
+        disp: DisplayContext,
+        columns: usize,
+        y: u32,
+        line: [u8; MAX_COLUMNS + 1],
+        len: usize,
+    }
+
+    impl CrashWriter {
+        fn flush_line(&mut self) {
+            if self.y + LINE_HEIGHT <= Display::height() {
+                self.line[self.len] = 0;
+                unsafe { bindings::graphics_draw_text(self.disp, GLYPH_WIDTH as c_int, self.y as c_int, self.line.as_ptr() as *const c_char); }
+            }
+
+            self.y += LINE_HEIGHT;
+            self.len = 0;
+        }
+    }
+
+    impl core::fmt::Write for CrashWriter {
+        fn write_str(&mut self, s: &str) -> core::fmt::Result {
+            for byte in s.bytes() {
+                if byte == b'\n' {
+                    self.flush_line();
+                    continue;
+                }
+
+                if self.len == self.columns {
+                    self.flush_line();
+                }
+
+                self.line[self.len] = byte;
+                self.len += 1;
+            }
+
+            return Ok(());
+        }
+    }
+
+    fn crash_screen(ex: &mut Exception) {
+        if !Display::is_initialized() {
+            Display::init(Resolution::RESOLUTION_320x240, BitDepth::DEPTH_16_BPP, 2, Gamma::GAMMA_NONE, AntiAlias::ANTIALIAS_RESAMPLE);
+        }
+
+        // Columns left after a one glyph margin on each side.
+        let columns = core::cmp::min((Display::width() / GLYPH_WIDTH) as usize - 2, MAX_COLUMNS);
+
+        GraphicsEngine::set_color(GraphicsEngine::make_color(255, 255, 255, 255), GraphicsEngine::make_color(0, 0, 0, 255));
+
+        // Swapping buffers needs the VI interrupt, which can't run here, so draw
+        // the same screen into every buffer and let whichever is showing stay up.
+        for disp in 1..=Display::buffer_count() as DisplayContext {
+            GraphicsEngine::fill_screen(disp, GraphicsEngine::make_color(0, 0, 0, 255));
+
+            let mut writer = CrashWriter { disp, columns, y: LINE_HEIGHT, line: [0; MAX_COLUMNS + 1], len: 0 };
+            let _ = dump(ex, &mut writer);
+
+            if writer.len > 0 {
+                writer.flush_line();
+            }
+        }
+
+        loop {}
+    }
+
+    /// Install an exception handler that shows a crash screen and halts.
+    ///
+    /// The handler initializes the display at 320x240 if it isn't already, draws
+    /// the output of dump() in white on black into every display buffer, and
+    /// spins forever. It never waits on an interrupt, since interrupts are
+    /// disabled in exception context; drawing into all buffers means the one
+    /// the video interface is currently showing has the dump without a swap.
+    /// This replaces any handler installed with set_handler().
+    pub fn install_crash_screen() {
+        set_handler(crash_screen);
+    }
+
+    /// The crash screen as a plain exception handler, for register_exception_handler().
+    ///
+    /// Behaves as install_crash_screen() describes. Useful where the handler has
+    /// to be a function pointer, e.g. to chain to it from another handler.
+    pub extern "C" fn crash_screen_handler(exception: *mut Exception) {
+        unsafe { crash_screen(&mut *exception); }
+    }
+
+    // break 0x1D1: SPECIAL opcode, BREAK function, code in bits 16-25. The code
+    // tells breakpoint() apart from other break instructions.
+    const BREAKPOINT_INSTRUCTION: u32 = 0x0000_000D | (0x1D1 << 16);
+
+    /// Trap into the exception handler with a message, like a hardware assert.
+    ///
+    /// Raises EXCEPTION_CODE_BREAKPOINT. Handlers get the message from
+    /// Exception::breakpoint_message(); dump() and the crash screen print it.
+    /// This never returns: if a handler resumes past the break, execution
+    /// spins in place.
+    pub fn breakpoint(msg: &'static str) -> ! {
+        #[cfg(target_arch = "mips")]
+        unsafe {
+            asm!(
+                "break 0x1D1",
+                "1: b 1b",
+                "nop",
+                in("$4") msg.as_ptr(),
+                in("$5") msg.len(),
+                options(noreturn)
+            );
+        }
+
+        #[cfg(not(target_arch = "mips"))]
+        panic!("Exceptions::breakpoint(): {}", msg);
+    }
+
+    /// Maximum size in bytes of a closure passed to set_handler().
+    pub const HANDLER_CAPACITY: usize = 64;
+
+    // The closure lives inline since there is no allocator to box it.
+    struct HandlerSlot {
+        storage: [u64; HANDLER_CAPACITY / 8],
+        call: Option<unsafe fn(*mut u8, &mut Exception)>,
+        drop: Option<unsafe fn(*mut u8)>,
+    }
+
+    static mut HANDLER: HandlerSlot = HandlerSlot {
+        storage: [0; HANDLER_CAPACITY / 8],
+        call: None,
+        drop: None,
+    };
 
-    #[repr(C)]
-    pub struct Exception {
-        pub _type: c_int,
-        pub code: ExceptionCode,
-        pub info: *const c_char,
-        pub regs: Volatile<RegisterBlock>
+    extern "C" fn handler_trampoline(exception: *mut Exception) {
+        unsafe {
+            if let (Some(call), Some(exception)) = (HANDLER.call, exception.as_mut()) {
+                call(HANDLER.storage.as_mut_ptr() as *mut u8, exception);
+            }
+        }
     }
 
-    #[repr(C)]
-    pub enum ExceptionType {
-        EXCEPTION_TYPE_UNKNOWN = 0,
-        EXCEPTION_TYPE_RESET,
-        EXCEPTION_TYPE_CRITICAL
+    // Drop the installed closure, if any. Exceptions can't be masked, so the
+    // trampoline's view of the closure is taken away before it is dropped.
+    unsafe fn release_closure() {
+        HANDLER.call = None;
+        compiler_fence(Ordering::SeqCst);
+
+        if let Some(drop) = HANDLER.drop.take() {
+            drop(HANDLER.storage.as_mut_ptr() as *mut u8);
+        }
     }
 
-    #[repr(C)]
-    pub enum ExceptionCode {
-        EXCEPTION_CODE_INTERRUPT = 0,
-        EXCEPTION_CODE_TLB_MODIFICATION = 1,
-        EXCEPTION_CODE_TLB_LOAD_I_MISS = 2,
-        EXCEPTION_CODE_TLB_STORE_MISS = 3,
-        EXCEPTION_CODE_LOAD_I_ADDRESS_ERROR = 4,
-        EXCEPTION_CODE_STORE_ADDRESS_ERROR = 5,
-        EXCEPTION_CODE_I_BUS_ERROR = 6,
-        EXCEPTION_CODE_D_BUS_ERROR = 7,
-        EXCEPTION_CODE_SYS_CALL = 8,
-        EXCEPTION_CODE_BREAKPOINT = 9,
-        EXCEPTION_CODE_RESERVED_INSTRUCTION = 10,
-        EXCEPTION_CODE_COPROCESSOR_UNUSABLE = 11,
-        EXCEPTION_CODE_ARITHMETIC_OVERFLOW = 12,
-        EXCEPTION_CODE_TRAP = 13,
-        EXCEPTION_CODE_FLOATING_POINT = 15,
-        EXCEPTION_CODE_WATCH = 23,
+    unsafe fn call_handler<F: FnMut(&mut Exception)>(storage: *mut u8, exception: &mut Exception) {
+        (*(storage as *mut F))(exception);
     }
 
-    #[repr(C)]
-    pub struct RegisterBlock {
-        pub gpr: [Volatile<c_ulong>; 32],
-        pub sr: Volatile<c_uint>,
-        pub cr: Volatile<c_uint>,
-        pub epc: Volatile<c_uint>,
-        pub hi: Volatile<c_ulong>,
-        pub lo: Volatile<c_ulong>,
-        pub fc31: Volatile<c_uint>,
-        pub fpr: [Volatile<c_ulong>; 32]
+    unsafe fn drop_handler<F>(storage: *mut u8) {
+        core::ptr::drop_in_place(storage as *mut F);
     }
 
-    /// Register an exception handler to handle exceptions.
-    ///
-    /// The registered handle is responsible for clearing any bits that may cause a re-trigger
-    /// of the same exception and updating the EPC. An important example is the cause bits (12-17)
-    /// of FCR31 from cop1. To prevent re-triggering the exception they should be cleared by the handler.
+    /// Register a closure to handle exceptions.
     ///
-    /// To manipulate the registers, update the values in the Exception struct. They will be restored
-    /// to appropriate locations when returning from the handler. Setting them directly will not work
-    /// as expected as they will get overwritten with the values pointed by the struct.
+    /// Only one handler is supported, matching libdragon's single slot: this
+    /// replaces and drops any previously set closure, as do
+    /// register_exception_handler() and clear_handler().
     ///
-    /// There is only one exception to this, cr (cause register) which is also modified by the int handler
-    /// before the saved values are restored thus it is only possible to update it through COP0::WRITE_CR()
-    /// macro if it is needed. This shouldn't be necessary though as they are already handled by the library.
+    /// The closure is stored inline, so it must fit in HANDLER_CAPACITY bytes
+    /// with at most 8-byte alignment (this panics otherwise). It receives the
+    /// exception as a safe reference; register changes made through it are
+    /// restored when the handler returns, as described for
+    /// register_exception_handler().
     ///
-    /// k0 ($26), k1 ($27) are not saved/restored and will not be available in the handler. Theoretically
-    /// we can exclude s0-s7 ($16-$23), and gp ($28) to gain some performance as they are already saved
-    /// by GCC when necessary. The same is true for sp ($29) and ra ($31) but current interrupt handler
-    /// manipulates them via allocating a new stack and doing a jal. Similarly floating point registers
-    /// f21-f31 are callee-saved. In the future we may consider removing them from the save state for
-    /// interrupts (but not for exceptions)
-    pub fn register_exception_handler(callback: extern "C" fn(*mut Exception)) {
-        unsafe { bindings::register_exception_handler(Some(callback)); }
-    }
+    /// Exception context is extremely constrained: interrupts are disabled, the
+    /// faulting code may hold any lock, and nothing may allocate. Do the minimum
+    /// and return, or halt. As with register_exception_handler(), the closure must
+    /// clear anything that would re-trigger the same exception (such as the FCR31
+    /// cause bits) and advance the EPC past the faulting instruction if it means
+    /// to resume.
+    pub fn set_handler<F: FnMut(&mut Exception) + Send + 'static>(f: F) {
+        assert!(core::mem::size_of::<F>() <= HANDLER_CAPACITY, "Exceptions::set_handler(): closure is {} bytes, at most {} fit", core::mem::size_of::<F>(), HANDLER_CAPACITY);
+        assert!(core::mem::align_of::<F>() <= core::mem::align_of::<u64>(), "Exceptions::set_handler(): closure alignment {} is too large", core::mem::align_of::<F>());
 
-    ///
-    pub fn default_exception_handler(exception: *mut Exception) {
-        unsafe { bindings::exception_default_handler(exception); }
+        unsafe {
+            release_closure();
+
+            core::ptr::write(HANDLER.storage.as_mut_ptr() as *mut F, f);
+            HANDLER.drop = Some(drop_handler::<F>);
+            compiler_fence(Ordering::SeqCst);
+            HANDLER.call = Some(call_handler::<F>);
+
+            bindings::register_exception_handler(Some(handler_trampoline));
+        }
     }
 }
 
@@ -2535,17 +7450,635 @@ pub mod Directory {
     #[macro_export] macro_rules! DT_REG {() => (1)} // Regular file
     #[macro_export] macro_rules! DT_DIR {() => (2)} // Directory
 
+    /// Decoded d_type of a directory entry.
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub enum EntryKind {
+        File,      // DT_REG
+        Directory, // DT_DIR
+        Unknown    // Any other d_type
+    }
+
+    impl DirType {
+        /// The entry name, up to the null terminator.
+        ///
+        /// A name with no terminator uses the whole array. Anything from the
+        /// first byte that isn't valid UTF-8 onwards is dropped.
+        pub fn name(&self) -> &str {
+            let bytes = unsafe { core::slice::from_raw_parts(self.d_name.as_ptr() as *const u8, self.d_name.len()) };
+            let len = bytes.iter().position(|byte| *byte == 0).unwrap_or(bytes.len());
+
+            return match core::str::from_utf8(&bytes[..len]) {
+                Ok(name) => name,
+                Err(err) => unsafe { core::str::from_utf8_unchecked(&bytes[..err.valid_up_to()]) }
+            };
+        }
+
+        /// Whether the entry is a file or a directory.
+        pub fn kind(&self) -> EntryKind {
+            return match self.d_type {
+                crate::DT_REG!() => EntryKind::File,
+                crate::DT_DIR!() => EntryKind::Directory,
+                _ => EntryKind::Unknown
+            };
+        }
+    }
+
     /// Find the first file in a directory.
     ///
-    /// Note: Path must be null-terminated.
+    /// Note: Path must be null-terminated. Prefer find_first_str() unless the
+    /// path is already a C string.
     pub fn find_first(path: &[c_uchar], dir: &mut DirType) -> c_int {
         unsafe { return bindings::dir_findfirst(path.as_ptr().cast(), dir); }
     }
 
     /// Find the next file in a directory.
     ///
-    /// Note: Path must be null-terminated.
+    /// Note: Path must be null-terminated. Prefer find_next_str() unless the
+    /// path is already a C string.
     pub fn find_next(path: &[c_uchar], dir: &mut DirType) -> c_int {
         unsafe { return bindings::dir_findnext(path.as_ptr().cast(), dir); }
     }
-}
\ No newline at end of file
+
+    /// Find the first file in a directory.
+    ///
+    /// The path is null-terminated internally. Returns PATH_TOO_LONG if it
+    /// doesn't fit in PATH_MAX bytes.
+    pub fn find_first_str(path: &str, dir: &mut DirType) -> c_int {
+        return match c_path(path) {
+            Ok(path) => find_first(&path, dir),
+            Err(error) => error
+        };
+    }
+
+    /// Find the next file in a directory.
+    ///
+    /// The path is null-terminated internally. Returns PATH_TOO_LONG if it
+    /// doesn't fit in PATH_MAX bytes.
+    pub fn find_next_str(path: &str, dir: &mut DirType) -> c_int {
+        return match c_path(path) {
+            Ok(path) => find_next(&path, dir),
+            Err(error) => error
+        };
+    }
+
+    /// Longest path, including its terminator, that the &str helpers accept.
+    pub const PATH_MAX: usize = 256;
+
+    /// Error yielded for a path of PATH_MAX bytes or more. libdragon never returns it.
+    pub const PATH_TOO_LONG: c_int = -256;
+
+    // Returned by dir_findfirst() for an empty directory and by dir_findnext()
+    // once there are no more entries.
+    const END_OF_DIRECTORY: c_int = -1;
+
+    // Copy a path into a null-terminated buffer.
+    fn c_path(path: &str) -> Result<[u8; PATH_MAX], c_int> {
+        if path.len() >= PATH_MAX {
+            return Err(PATH_TOO_LONG);
+        }
+
+        let mut buffer = [0; PATH_MAX];
+        buffer[..path.len()].copy_from_slice(path.as_bytes());
+
+        return Ok(buffer);
+    }
+
+    fn empty_entry() -> DirType {
+        return DirType { d_name: [0; 256], d_type: 0 };
+    }
+
+    /// Iterator over the entries of a directory, created by read_dir().
+    pub struct ReadDir {
+        path: [u8; PATH_MAX],
+        started: bool,
+        error: Option<c_int>,
+        done: bool,
+    }
+
+    impl Iterator for ReadDir {
+        type Item = Result<DirType, c_int>;
+
+        fn next(&mut self) -> Option<Result<DirType, c_int>> {
+            if self.done {
+                return None;
+            }
+
+            if let Some(error) = self.error {
+                self.done = true;
+                return Some(Err(error));
+            }
+
+            let mut entry = empty_entry();
+            let result = match self.started {
+                false => find_first(&self.path, &mut entry),
+                true => find_next(&self.path, &mut entry)
+            };
+            self.started = true;
+
+            return match result {
+                0 => Some(Ok(entry)),
+                END_OF_DIRECTORY => {
+                    self.done = true;
+                    None
+                },
+                error => {
+                    self.done = true;
+                    Some(Err(error))
+                }
+            };
+        }
+    }
+
+    /// Iterate over the entries of a directory.
+    ///
+    /// Loops dir_findfirst()/dir_findnext() internally and takes care of the
+    /// path's null termination. Iteration ends at the end of the directory; an
+    /// error from libdragon, or PATH_TOO_LONG, is yielded once and ends it too.
+    pub fn read_dir(path: &str) -> ReadDir {
+        return match c_path(path) {
+            Ok(path) => ReadDir { path, started: false, error: None, done: false },
+            Err(error) => ReadDir { path: [0; PATH_MAX], started: false, error: Some(error), done: false }
+        };
+    }
+
+    /// Count the entries of a directory.
+    pub fn count_entries(path: &str) -> Result<usize, c_int> {
+        let mut count = 0;
+        for entry in read_dir(path) {
+            entry?;
+            count += 1;
+        }
+
+        return Ok(count);
+    }
+
+    /// Count the files and directories of a directory, as (files, dirs).
+    ///
+    /// Entries of any other kind are counted in neither.
+    pub fn count_by_kind(path: &str) -> Result<(usize, usize), c_int> {
+        let (mut files, mut dirs) = (0, 0);
+        for entry in read_dir(path) {
+            match entry?.kind() {
+                EntryKind::File => files += 1,
+                EntryKind::Directory => dirs += 1,
+                EntryKind::Unknown => {}
+            }
+        }
+
+        return Ok((files, dirs));
+    }
+
+    /// Find the entry of a directory with the given name (case-sensitive).
+    ///
+    /// Each call starts a fresh scan with dir_findfirst(), so it's safe to use
+    /// after an unfinished listing of another directory.
+    pub fn find(path: &str, name: &str) -> Result<Option<DirType>, c_int> {
+        for entry in read_dir(path) {
+            let entry = entry?;
+            if entry.name() == name {
+                return Ok(Some(entry));
+            }
+        }
+
+        return Ok(None);
+    }
+
+    /// Deepest level of subdirectories walk() descends into.
+    pub const MAX_WALK_DEPTH: usize = 8;
+
+    /// A full path yielded by walk().
+    #[derive(Clone, Copy)]
+    pub struct WalkPath {
+        buffer: [u8; PATH_MAX],
+        len: usize,
+    }
+
+    impl WalkPath {
+        /// The path as a string slice.
+        pub fn as_str(&self) -> &str {
+            // Built only from a &str path and entry names checked by DirType::name().
+            unsafe { return core::str::from_utf8_unchecked(&self.buffer[..self.len]); }
+        }
+    }
+
+    impl core::ops::Deref for WalkPath {
+        type Target = str;
+
+        fn deref(&self) -> &str {
+            return self.as_str();
+        }
+    }
+
+    impl core::fmt::Debug for WalkPath {
+        fn fmt(&self, f: &mut core::fmt::Formatter) -> core::fmt::Result {
+            return core::fmt::Debug::fmt(self.as_str(), f);
+        }
+    }
+
+    #[derive(Clone, Copy)]
+    struct WalkLevel {
+        dir_len: usize,  // Length of this directory's path in Walk::path
+        consumed: usize, // Entries already yielded from this directory
+    }
+
+    /// Depth-first iterator over a directory tree, created by walk().
+    pub struct Walk {
+        path: [u8; PATH_MAX],
+        levels: [WalkLevel; MAX_WALK_DEPTH + 1],
+        depth: usize,
+        session: Option<usize>,
+        error: Option<c_int>,
+    }
+
+    // Append "/name" (or just "name" after a trailing slash) to the first len bytes of buffer.
+    fn join(buffer: &mut [u8; PATH_MAX], len: usize, name: &str) -> Result<usize, c_int> {
+        let separator = match len > 0 && buffer[len - 1] != b'/' {
+            true => 1,
+            false => 0
+        };
+        let joined = len + separator + name.len();
+
+        if joined >= PATH_MAX {
+            return Err(PATH_TOO_LONG);
+        }
+
+        if separator == 1 {
+            buffer[len] = b'/';
+        }
+        buffer[len + separator..joined].copy_from_slice(name.as_bytes());
+        buffer[joined] = 0;
+
+        return Ok(joined);
+    }
+
+    impl Walk {
+        // Fetch the next entry of the directory at level. libdragon keeps one
+        // findfirst/findnext session at a time, so a directory whose session was
+        // interrupted by a subdirectory is rescanned past the consumed entries.
+        fn next_entry(&mut self, level: usize, entry: &mut DirType) -> c_int {
+            let WalkLevel { dir_len, consumed } = self.levels[level];
+            self.path[dir_len] = 0;
+
+            if self.session == Some(level) {
+                return find_next(&self.path, entry);
+            }
+
+            self.session = Some(level);
+
+            let mut result = find_first(&self.path, entry);
+            for _ in 0..consumed {
+                if result != 0 {
+                    break;
+                }
+
+                result = find_next(&self.path, entry);
+            }
+
+            return result;
+        }
+    }
+
+    impl Iterator for Walk {
+        type Item = Result<(WalkPath, EntryKind), c_int>;
+
+        fn next(&mut self) -> Option<Result<(WalkPath, EntryKind), c_int>> {
+            if let Some(error) = self.error.take() {
+                return Some(Err(error));
+            }
+
+            while self.depth > 0 {
+                let level = self.depth - 1;
+                let mut entry = empty_entry();
+
+                match self.next_entry(level, &mut entry) {
+                    0 => {},
+                    END_OF_DIRECTORY => {
+                        self.depth -= 1;
+                        self.session = None;
+                        continue;
+                    },
+                    error => {
+                        self.depth -= 1;
+                        self.session = None;
+                        return Some(Err(error));
+                    }
+                }
+
+                self.levels[level].consumed += 1;
+
+                let name = entry.name();
+                if name == "." || name == ".." {
+                    continue;
+                }
+
+                let mut found = WalkPath { buffer: self.path, len: 0 };
+                found.len = match join(&mut found.buffer, self.levels[level].dir_len, name) {
+                    Ok(len) => len,
+                    Err(error) => return Some(Err(error))
+                };
+
+                let kind = entry.kind();
+                if kind == EntryKind::Directory && level < MAX_WALK_DEPTH {
+                    self.path = found.buffer;
+                    self.levels[level + 1] = WalkLevel { dir_len: found.len, consumed: 0 };
+                    self.depth += 1;
+                }
+
+                return Some(Ok((found, kind)));
+            }
+
+            return None;
+        }
+    }
+
+    /// Walk a directory tree depth-first, yielding the full path of every entry.
+    ///
+    /// A directory is yielded before its contents. Subdirectories more than
+    /// MAX_WALK_DEPTH levels below path are yielded but not entered, which bounds
+    /// the walk on malformed filesystems. Nothing is allocated; entries that would
+    /// make a path of PATH_MAX bytes or more yield PATH_TOO_LONG and are skipped,
+    /// and a directory that fails to read yields its error and is skipped.
+    pub fn walk(path: &str) -> Walk {
+        let mut walk = Walk {
+            path: [0; PATH_MAX],
+            levels: [WalkLevel { dir_len: 0, consumed: 0 }; MAX_WALK_DEPTH + 1],
+            depth: 0,
+            session: None,
+            error: None,
+        };
+
+        match c_path(path) {
+            Ok(buffer) => {
+                walk.path = buffer;
+                walk.levels[0].dir_len = path.len();
+                walk.depth = 1;
+            },
+            Err(error) => walk.error = Some(error)
+        }
+
+        return walk;
+    }
+}
+
+/// Real-time clock support.
+///
+/// Some cartridges (and flashcarts emulating them) carry a battery-backed
+/// real-time clock on the Joybus, as used by the 64DD and Animal Forest. Call
+/// init() once to detect it; the other functions do nothing useful if it
+/// returned false.
+pub mod RealTimeClock {
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::bindings;
+
+    /// A calendar date and time as kept by the RTC.
+    #[repr(C)]
+    #[derive(Clone, Copy, Debug, PartialEq, Eq)]
+    pub struct RtcTime {
+        pub year: u16,  // Full year, e.g. 2021
+        pub month: u8,  // 0-11
+        pub day: u8,    // 1-31
+        pub hour: u8,   // 0-23
+        pub min: u8,    // 0-59
+        pub sec: u8,    // 0-59
+        pub dow: u8     // Day of week, 0-6 with 0 being Sunday
+    }
+
+    static PRESENT: AtomicBool = AtomicBool::new(false);
+
+    /// Detect and initialize the real-time clock.
+    ///
+    /// Returns whether an RTC was found.
+    pub fn init() -> bool {
+        let present = unsafe { bindings::rtc_init() };
+        PRESENT.store(present, Ordering::Relaxed);
+
+        return present;
+    }
+
+    /// Return whether the last init() found an RTC.
+    pub fn is_present() -> bool {
+        return PRESENT.load(Ordering::Relaxed);
+    }
+
+    /// Read the current time, or None if there is no RTC or the read failed.
+    pub fn get() -> Option<RtcTime> {
+        if !is_present() {
+            return None;
+        }
+
+        let mut time = RtcTime { year: 0, month: 0, day: 0, hour: 0, min: 0, sec: 0, dow: 0 };
+
+        unsafe {
+            return match bindings::rtc_get(&mut time) {
+                true => Some(time),
+                false => None
+            };
+        }
+    }
+
+    /// Set the current time. Returns whether the RTC accepted it.
+    pub fn set(time: &RtcTime) -> bool {
+        if !is_present() {
+            return false;
+        }
+
+        let mut time = *time;
+        unsafe { return bindings::rtc_set(&mut time); }
+    }
+}
+
+/// One-call bring-up of the common subsystems.
+///
+/// Several libdragon subsystems depend on others being initialized first (the
+/// display, RDP, audio and timers all register interrupt handlers), and the
+/// console and display can't be used together. boot() initializes whatever a
+/// BootConfig asks for in an order that respects this, and the returned System
+/// closes them again in reverse when dropped.
+pub mod System {
+    use core::sync::atomic::{AtomicBool, Ordering};
+
+    use crate::{Audio, Console::{self, RenderMode}, Controller, Display::{self, AntiAlias, BitDepth, Gamma, Resolution}, DragonFS, Error, Interrupt, RDP, RSP, Timer};
+
+    /// What drives the screen. The console and display are mutually exclusive.
+    pub enum Video {
+        None,
+        Console(RenderMode),
+        Display { res: Resolution, bitdepth: BitDepth, buffers: u32, gamma: Gamma, aa: AntiAlias }
+    }
+
+    /// The subsystems boot() should bring up. Everything is off by default.
+    pub struct BootConfig {
+        pub video: Video,
+        pub rdp: bool,              // Requires Video::Display
+        pub rsp: bool,
+        pub audio: Option<(Audio::Frequency, i32)>, // Frequency and number of buffers
+        pub controller: bool,
+        pub timer: bool,
+        pub dfs: Option<u32>        // Filesystem base, usually DFS_DEFAULT_LOCATION!()
+    }
+
+    impl BootConfig {
+        pub const fn new() -> BootConfig {
+            return BootConfig {
+                video: Video::None,
+                rdp: false,
+                rsp: false,
+                audio: None,
+                controller: false,
+                timer: false,
+                dfs: None
+            };
+        }
+
+        pub fn console(mut self, mode: RenderMode) -> BootConfig {
+            self.video = Video::Console(mode);
+            return self;
+        }
+
+        pub fn display(mut self, res: Resolution, bitdepth: BitDepth, buffers: u32, gamma: Gamma, aa: AntiAlias) -> BootConfig {
+            self.video = Video::Display { res, bitdepth, buffers, gamma, aa };
+            return self;
+        }
+
+        pub fn rdp(mut self) -> BootConfig {
+            self.rdp = true;
+            return self;
+        }
+
+        pub fn rsp(mut self) -> BootConfig {
+            self.rsp = true;
+            return self;
+        }
+
+        pub fn audio(mut self, frequency: Audio::Frequency, numbuffers: i32) -> BootConfig {
+            self.audio = Some((frequency, numbuffers));
+            return self;
+        }
+
+        pub fn controller(mut self) -> BootConfig {
+            self.controller = true;
+            return self;
+        }
+
+        pub fn timer(mut self) -> BootConfig {
+            self.timer = true;
+            return self;
+        }
+
+        pub fn dfs(mut self, base_fs_location: u32) -> BootConfig {
+            self.dfs = Some(base_fs_location);
+            return self;
+        }
+    }
+
+    static BOOTED: AtomicBool = AtomicBool::new(false);
+
+    /// Guard for the subsystems started by boot(). Dropping it closes them.
+    pub struct System {
+        console: bool,
+        display: bool,
+        rdp: bool,
+        audio: bool,
+        timer: bool
+    }
+
+    /// Initialize the subsystems in config.
+    ///
+    /// The order is interrupts, timer, filesystem, console or display, RDP, RSP,
+    /// audio, then controllers. Only one System may exist at a time; booting
+    /// again before the previous one is dropped panics, as does asking for the
+    /// RDP without Video::Display. If the filesystem fails to mount, everything
+    /// started so far is closed again and the error is returned.
+    pub fn boot(config: BootConfig) -> Result<System, Error> {
+        assert!(!BOOTED.swap(true, Ordering::Relaxed), "System::boot() called while a System is alive");
+
+        let is_display = match config.video {
+            Video::Display { .. } => true,
+            _ => false
+        };
+        assert!(!config.rdp || is_display, "BootConfig::rdp requires Video::Display");
+
+        let mut system = System { console: false, display: false, rdp: false, audio: false, timer: false };
+
+        Interrupt::init();
+
+        if config.timer {
+            Timer::init();
+            system.timer = true;
+        }
+
+        if let Some(base_fs_location) = config.dfs {
+            match DragonFS::init(base_fs_location) {
+                DragonFS::DFSResult::Success => {},
+                error => return Err(Error::from(error))
+            }
+        }
+
+        match config.video {
+            Video::None => {},
+            Video::Console(mode) => {
+                Console::init();
+                Console::set_render_mode(mode);
+                system.console = true;
+            },
+            Video::Display { res, bitdepth, buffers, gamma, aa } => {
+                Display::init(res, bitdepth, buffers, gamma, aa);
+                system.display = true;
+            }
+        }
+
+        if config.rdp {
+            RDP::init();
+            system.rdp = true;
+        }
+
+        if config.rsp {
+            RSP::init();
+        }
+
+        if let Some((frequency, numbuffers)) = config.audio {
+            Audio::init(frequency, numbuffers);
+            system.audio = true;
+        }
+
+        if config.controller {
+            Controller::init();
+        }
+
+        return Ok(system);
+    }
+
+    impl Drop for System {
+        fn drop(&mut self) {
+            if self.audio {
+                Audio::close();
+            }
+            if self.rdp {
+                RDP::close();
+            }
+            if self.display {
+                Display::close();
+            }
+            if self.console {
+                Console::close();
+            }
+            if self.timer {
+                Timer::timer_close();
+            }
+
+            BOOTED.store(false, Ordering::Relaxed);
+        }
+    }
+}
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn recommended_audio_frequency_per_region() {
+        assert_eq!(Region::Pal.recommended_audio_frequency(), 44100);
+        assert_eq!(Region::Ntsc.recommended_audio_frequency(), 44100);
+        assert_eq!(Region::Mpal.recommended_audio_frequency(), 32000);
+    }
+}